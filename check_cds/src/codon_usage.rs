@@ -0,0 +1,122 @@
+use crate::genetic_code::GeneticCode;
+
+// 64个确定密码子在读码框内的计数，按TCAG表顺序排列
+pub struct CodonCounts {
+    counts: [u64; 64],
+}
+
+// 密码子使用表的一行：密码子、计数、千分频率和RSCU
+pub struct CodonUsageRow {
+    pub codon: [u8; 3],
+    pub amino_acid: u8,
+    pub count: u64,
+    pub per_thousand: f64,
+    // 该氨基酸的密码子一个都没有出现时为None
+    pub rscu: Option<f64>,
+}
+
+impl Default for CodonCounts {
+    fn default() -> CodonCounts {
+        CodonCounts { counts: [0; 64] }
+    }
+}
+
+impl CodonCounts {
+    // 统计第0读码框中的每个完整密码子，含简并碱基的密码子跳过
+    pub fn from_sequence(sequence: &str) -> CodonCounts {
+        let mut counts = CodonCounts::default();
+        for codon in sequence.as_bytes().chunks_exact(3) {
+            if let Some(index) = GeneticCode::codon_index(codon) {
+                counts.counts[index] += 1;
+            }
+        }
+        counts
+    }
+
+    pub fn add(&mut self, other: &CodonCounts) {
+        for (total, count) in self.counts.iter_mut().zip(other.counts) {
+            *total += count;
+        }
+    }
+
+    // RSCU为该密码子的计数除以同一氨基酸（按所选密码表）各密码子的平均计数，
+    // 无偏好的密码子RSCU为1
+    pub fn usage(&self, code: &GeneticCode) -> Vec<CodonUsageRow> {
+        let total: u64 = self.counts.iter().sum();
+
+        (0..64)
+            .map(|index| {
+                let amino_acid = code.amino_acid_at(index);
+                let synonymous: Vec<u64> = (0..64)
+                    .filter(|&other| code.amino_acid_at(other) == amino_acid)
+                    .map(|other| self.counts[other])
+                    .collect();
+                let family_total: u64 = synonymous.iter().sum();
+                let count = self.counts[index];
+
+                CodonUsageRow {
+                    codon: GeneticCode::codon_at(index),
+                    amino_acid,
+                    count,
+                    per_thousand: if total == 0 { 0.0 } else { count as f64 * 1000.0 / total as f64 },
+                    rscu: if family_total == 0 {
+                        None
+                    } else {
+                        Some(count as f64 * synonymous.len() as f64 / family_total as f64)
+                    },
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row<'a>(rows: &'a [CodonUsageRow], codon: &[u8]) -> &'a CodonUsageRow {
+        &rows[GeneticCode::codon_index(codon).unwrap()]
+    }
+
+    #[test]
+    fn test_counts_skip_ambiguous_and_partial_codons() {
+        let counts = CodonCounts::from_sequence("ATGGCNGCTGC");
+        let code = GeneticCode::from_table(1).unwrap();
+        let rows = counts.usage(&code);
+        assert_eq!(rows.iter().map(|row| row.count).sum::<u64>(), 2);
+        assert_eq!(row(&rows, b"ATG").count, 1);
+        assert_eq!(row(&rows, b"GCT").count, 1);
+    }
+
+    #[test]
+    fn test_rscu_and_frequency() {
+        let code = GeneticCode::from_table(1).unwrap();
+        let mut counts = CodonCounts::from_sequence("GCTGCTGCTGCC");
+        counts.add(&CodonCounts::from_sequence("ATGTGGAAA"));
+        let rows = counts.usage(&code);
+
+        // 丙氨酸有4个同义密码子：GCT出现3次、GCC出现1次，平均每个密码子1次
+        assert_eq!(row(&rows, b"GCT").rscu, Some(3.0));
+        assert_eq!(row(&rows, b"GCC").rscu, Some(1.0));
+        assert_eq!(row(&rows, b"GCA").rscu, Some(0.0));
+        // 单密码子氨基酸的RSCU总是1，没有出现的氨基酸为None
+        assert_eq!(row(&rows, b"ATG").rscu, Some(1.0));
+        assert_eq!(row(&rows, b"TGG").rscu, Some(1.0));
+        assert_eq!(row(&rows, b"AAG").rscu, Some(0.0));
+        assert_eq!(row(&rows, b"TTT").rscu, None);
+
+        assert_eq!(row(&rows, b"GCT").per_thousand, 3.0 * 1000.0 / 7.0);
+        assert_eq!(row(&rows, b"GCT").amino_acid, b'A');
+    }
+
+    #[test]
+    fn test_rscu_follows_genetic_code() {
+        // 表2中AGA为终止密码子，精氨酸只剩4个同义密码子
+        let counts = CodonCounts::from_sequence("CGTAGA");
+        let standard = counts.usage(&GeneticCode::from_table(1).unwrap());
+        assert_eq!(row(&standard, b"CGT").rscu, Some(3.0));
+        let mito = counts.usage(&GeneticCode::from_table(2).unwrap());
+        assert_eq!(row(&mito, b"CGT").rscu, Some(4.0));
+        assert_eq!(row(&mito, b"AGA").amino_acid, b'*');
+    }
+}
@@ -0,0 +1,143 @@
+use crate::genetic_code::GeneticCode;
+
+// 含有A、C、G、T以外字符的序列的处理方式
+#[derive(Clone, Copy, PartialEq)]
+pub enum IllegalAction {
+    Drop,
+    Mask,
+}
+
+impl IllegalAction {
+    pub fn from_name(name: &str) -> Option<IllegalAction> {
+        match name {
+            "drop" => Some(IllegalAction::Drop),
+            "mask" => Some(IllegalAction::Mask),
+            _ => None,
+        }
+    }
+}
+
+pub struct FixOptions {
+    pub illegal: IllegalAction,
+    pub trim_to_start: bool,
+}
+
+// 修复后的CDS（序列被丢弃时为None）以及每一处修改的说明
+pub struct FixResult {
+    pub sequence: Option<String>,
+    pub edits: Vec<(&'static str, String)>,
+}
+
+pub fn fix_sequence(sequence: &str, code: &GeneticCode, options: &FixOptions) -> FixResult {
+    let mut edits = Vec::new();
+    let mut bases = sequence.as_bytes().to_vec();
+
+    // 非法字符：丢弃整条序列，或者把每个非法字符替换为N
+    let illegal: Vec<usize> = bases
+        .iter()
+        .enumerate()
+        .filter(|(_, base)| !b"ATCG".contains(&base.to_ascii_uppercase()))
+        .map(|(offset, _)| offset)
+        .collect();
+    if !illegal.is_empty() {
+        let positions = illegal.iter().map(|offset| (offset + 1).to_string()).collect::<Vec<_>>().join(",");
+        if options.illegal == IllegalAction::Drop {
+            edits.push(("dropped", format!("{} illegal bases at {}", illegal.len(), positions)));
+            return FixResult { sequence: None, edits };
+        }
+        for &offset in &illegal {
+            bases[offset] = b'N';
+        }
+        edits.push(("masked_illegal_bases", format!("{} bases at {} set to N", illegal.len(), positions)));
+    }
+
+    // 可选：去掉第一个读码框内起始密码子之前的碱基
+    if options.trim_to_start {
+        match bases.chunks_exact(3).position(|codon| code.is_start(codon)) {
+            Some(0) => {}
+            Some(index) => {
+                bases.drain(..index * 3);
+                edits.push(("trimmed_to_start", format!("removed {} bases before codon {}", index * 3, index + 1)));
+            }
+            None => edits.push(("no_start_found", "sequence left untrimmed".to_string())),
+        }
+    }
+
+    // 保留到第一个读码框内终止密码子为止（含终止密码子）
+    if let Some(index) = bases.chunks_exact(3).position(|codon| code.is_stop(codon)) {
+        let end = (index + 1) * 3;
+        if end < bases.len() {
+            edits.push(("truncated_at_stop", format!("removed {} bases after stop codon {}", bases.len() - end, index + 1)));
+            bases.truncate(end);
+        }
+    }
+
+    // 末尾不足一个密码子的碱基
+    let remainder = bases.len() % 3;
+    if remainder != 0 {
+        bases.truncate(bases.len() - remainder);
+        edits.push(("trimmed_partial_codon", format!("removed {} trailing bases", remainder)));
+    }
+
+    FixResult {
+        sequence: Some(String::from_utf8_lossy(&bases).into_owned()),
+        edits,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fix(sequence: &str, illegal: IllegalAction, trim_to_start: bool) -> FixResult {
+        let code = GeneticCode::from_table(1).unwrap();
+        fix_sequence(sequence, &code, &FixOptions { illegal, trim_to_start })
+    }
+
+    fn edit_kinds(result: &FixResult) -> Vec<&'static str> {
+        result.edits.iter().map(|(kind, _)| *kind).collect()
+    }
+
+    #[test]
+    fn test_valid_sequence_is_unchanged() {
+        let result = fix("ATGAAATAA", IllegalAction::Mask, true);
+        assert_eq!(result.sequence.as_deref(), Some("ATGAAATAA"));
+        assert!(result.edits.is_empty());
+    }
+
+    #[test]
+    fn test_illegal_bases_masked_or_dropped() {
+        let result = fix("ATGRAA-CCTAA", IllegalAction::Mask, false);
+        assert_eq!(result.sequence.as_deref(), Some("ATGNAANCCTAA"));
+        assert_eq!(result.edits[0], ("masked_illegal_bases", "2 bases at 4,7 set to N".to_string()));
+
+        let result = fix("ATGRAATAA", IllegalAction::Drop, false);
+        assert!(result.sequence.is_none());
+        assert_eq!(edit_kinds(&result), vec!["dropped"]);
+    }
+
+    #[test]
+    fn test_trim_to_start() {
+        let result = fix("CCCGGGATGAAATAA", IllegalAction::Mask, true);
+        assert_eq!(result.sequence.as_deref(), Some("ATGAAATAA"));
+        assert_eq!(edit_kinds(&result), vec!["trimmed_to_start"]);
+
+        // 没有选--trim-to-start时不裁剪；找不到起始密码子时保留原序列
+        let result = fix("CCCGGGATGAAATAA", IllegalAction::Mask, false);
+        assert_eq!(result.sequence.as_deref(), Some("CCCGGGATGAAATAA"));
+        let result = fix("CCCGGGTAA", IllegalAction::Mask, true);
+        assert_eq!(result.sequence.as_deref(), Some("CCCGGGTAA"));
+        assert_eq!(edit_kinds(&result), vec!["no_start_found"]);
+    }
+
+    #[test]
+    fn test_truncate_at_stop_and_partial_codon() {
+        let result = fix("ATGTAAGGGCC", IllegalAction::Mask, false);
+        assert_eq!(result.sequence.as_deref(), Some("ATGTAA"));
+        assert_eq!(edit_kinds(&result), vec!["truncated_at_stop"]);
+
+        let result = fix("ATGAAAGG", IllegalAction::Mask, false);
+        assert_eq!(result.sequence.as_deref(), Some("ATGAAA"));
+        assert_eq!(result.edits[0], ("trimmed_partial_codon", "removed 2 trailing bases".to_string()));
+    }
+}
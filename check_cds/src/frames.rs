@@ -0,0 +1,148 @@
+use crate::genetic_code::GeneticCode;
+use seq_utils::complement;
+
+// 六个读码框之一中不含终止密码子的最长密码子区间
+pub struct FrameOrf {
+    pub reverse: bool,
+    // 读码框第一个密码子之前跳过的碱基数：0、1或2
+    pub offset: usize,
+    pub codons: usize,
+    // 在输入CDS上的位置（正向，从1开始，闭区间）
+    pub start: usize,
+    pub end: usize,
+}
+
+impl FrameOrf {
+    pub fn strand(&self) -> char {
+        if self.reverse { '-' } else { '+' }
+    }
+
+    // 读码框标记，采用常用的+1..+3 / -1..-3写法
+    pub fn frame(&self) -> String {
+        format!("{}{}", self.strand(), self.offset + 1)
+    }
+
+    // 根据最佳读码框对注释模型给出的判断
+    pub fn diagnosis(&self) -> &'static str {
+        match (self.reverse, self.offset) {
+            (false, 0) => "annotated_frame",
+            (false, 1) => "shifted_by_1",
+            (false, _) => "shifted_by_2",
+            (true, 0) => "reverse_complemented",
+            (true, 1) => "reverse_complemented_shifted_by_1",
+            (true, _) => "reverse_complemented_shifted_by_2",
+        }
+    }
+}
+
+// 六个读码框各自的最长ORF，注释读码框（+1）排在第一个
+pub fn scan_frames(sequence: &str, code: &GeneticCode) -> Vec<FrameOrf> {
+    let forward = sequence.as_bytes();
+    let reverse: Vec<u8> = forward.iter().rev().map(|&base| complement(base)).collect();
+    let mut orfs = Vec::with_capacity(6);
+
+    for (is_reverse, bases) in [(false, forward), (true, reverse.as_slice())] {
+        for offset in 0..3 {
+            let mut orf = longest_orf(bases, offset, code);
+            orf.reverse = is_reverse;
+            if is_reverse && orf.codons > 0 {
+                let (start, end) = (orf.start, orf.end);
+                orf.start = bases.len() - end + 1;
+                orf.end = bases.len() - start + 1;
+            }
+            orfs.push(orf);
+        }
+    }
+
+    orfs
+}
+
+// ORF最长的读码框；长度相同时优先注释读码框
+pub fn best_frame(orfs: &[FrameOrf]) -> &FrameOrf {
+    let mut best = &orfs[0];
+    for orf in &orfs[1..] {
+        if orf.codons > best.codons {
+            best = orf;
+        }
+    }
+    best
+}
+
+fn longest_orf(bases: &[u8], offset: usize, code: &GeneticCode) -> FrameOrf {
+    let mut best = (0, 0);
+    let mut run_start = 0;
+    let codons: Vec<&[u8]> = bases.get(offset..).unwrap_or_default().chunks_exact(3).collect();
+
+    for (index, codon) in codons.iter().enumerate() {
+        if code.is_stop(codon) {
+            run_start = index + 1;
+        } else if index + 1 - run_start > best.1 - best.0 {
+            best = (run_start, index + 1);
+        }
+    }
+
+    let length = best.1 - best.0;
+    FrameOrf {
+        reverse: false,
+        offset,
+        codons: length,
+        start: if length == 0 { 0 } else { offset + best.0 * 3 + 1 },
+        end: if length == 0 { 0 } else { offset + best.1 * 3 },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use seq_utils::reverse_complement;
+
+    // 只有+2读码框（跳过1个碱基）没有终止密码子
+    const SHIFTED: &str = "TGGACTTAATCAGCTAACGCTTAA";
+
+    #[test]
+    fn test_scan_frames() {
+        let code = GeneticCode::from_table(1).unwrap();
+        let orfs = scan_frames(SHIFTED, &code);
+        let summary: Vec<(String, usize, usize, usize)> = orfs.iter()
+            .map(|orf| (orf.frame(), orf.codons, orf.start, orf.end))
+            .collect();
+        assert_eq!(summary, vec![
+            ("+1".to_string(), 4, 10, 21),
+            ("+2".to_string(), 7, 2, 22),
+            ("+3".to_string(), 4, 3, 14),
+            ("-1".to_string(), 4, 13, 24),
+            ("-2".to_string(), 4, 9, 20),
+            ("-3".to_string(), 4, 2, 13),
+        ]);
+
+        let best = best_frame(&orfs);
+        assert_eq!(best.frame(), "+2");
+        assert_eq!(best.diagnosis(), "shifted_by_1");
+    }
+
+    #[test]
+    fn test_reverse_complemented_model() {
+        let code = GeneticCode::from_table(1).unwrap();
+        let sequence = reverse_complement(SHIFTED);
+        let orfs = scan_frames(&sequence, &code);
+        let best = best_frame(&orfs);
+        assert_eq!(best.diagnosis(), "reverse_complemented_shifted_by_1");
+        // 位置换算到输入序列的正向坐标
+        assert_eq!((best.start, best.end), (3, 23));
+    }
+
+    #[test]
+    fn test_annotated_frame_wins_ties() {
+        let code = GeneticCode::from_table(1).unwrap();
+        // 六个读码框的最长ORF都是2个密码子
+        let orfs = scan_frames("ATGTTATAA", &code);
+        assert!(orfs.iter().all(|orf| orf.codons <= 2));
+        let best = best_frame(&orfs);
+        assert_eq!(best.diagnosis(), "annotated_frame");
+        assert_eq!((best.codons, best.start, best.end), (2, 1, 6));
+
+        // 太短的序列所有读码框都没有ORF
+        let orfs = scan_frames("AT", &code);
+        assert!(orfs.iter().all(|orf| orf.codons == 0 && orf.start == 0));
+    }
+}
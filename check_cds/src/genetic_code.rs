@@ -0,0 +1,334 @@
+// NCBI密码表，见 https://www.ncbi.nlm.nih.gov/Taxonomy/Utils/wprintgc.cgi
+//
+// 每张表按NCBI发布的格式书写：64个氨基酸和64个起始标记，
+// 密码子按TTT、TTC、TTA、TTG、TCT……GGG排列（碱基按TCAG顺序）
+
+const TABLES: [(u8, &str, &str, &str); 27] = [
+    (1, "Standard",
+        "FFLLSSSSYY**CC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        "---M------**--*----M---------------M----------------------------"),
+    (2, "Vertebrate Mitochondrial",
+        "FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNKKSS**VVVVAAAADDEEGGGG",
+        "----------**--------------------MMMM----------**---M------------"),
+    (3, "Yeast Mitochondrial",
+        "FFLLSSSSYY**CCWWTTTTPPPPHHQQRRRRIIMMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        "----------**----------------------MM---------------M------------"),
+    (4, "Mold, Protozoan, and Coelenterate Mitochondrial; Mycoplasma; Spiroplasma",
+        "FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        "--MM------**-------M------------MMMM---------------M------------"),
+    (5, "Invertebrate Mitochondrial",
+        "FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNKKSSSSVVVVAAAADDEEGGGG",
+        "---M------**--------------------MMMM---------------M------------"),
+    (6, "Ciliate, Dasycladacean and Hexamita Nuclear",
+        "FFLLSSSSYYQQCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        "--------------*--------------------M----------------------------"),
+    (9, "Echinoderm and Flatworm Mitochondrial",
+        "FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNNKSSSSVVVVAAAADDEEGGGG",
+        "----------**-----------------------M---------------M------------"),
+    (10, "Euplotid Nuclear",
+        "FFLLSSSSYY**CCCWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        "----------**-----------------------M----------------------------"),
+    (11, "Bacterial, Archaeal and Plant Plastid",
+        "FFLLSSSSYY**CC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        "---M------**--*----M------------MMMM---------------M------------"),
+    (12, "Alternative Yeast Nuclear",
+        "FFLLSSSSYY**CC*WLLLSPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        "----------**--*----M---------------M----------------------------"),
+    (13, "Ascidian Mitochondrial",
+        "FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNKKSSGGVVVVAAAADDEEGGGG",
+        "---M------**----------------------MM---------------M------------"),
+    (14, "Alternative Flatworm Mitochondrial",
+        "FFLLSSSSYYY*CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNNKSSSSVVVVAAAADDEEGGGG",
+        "-----------*-----------------------M----------------------------"),
+    (15, "Blepharisma Macronuclear",
+        "FFLLSSSSYY*QCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        "----------*---*--------------------M----------------------------"),
+    (16, "Chlorophycean Mitochondrial",
+        "FFLLSSSSYY*LCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        "----------*---*--------------------M----------------------------"),
+    (21, "Trematode Mitochondrial",
+        "FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNNKSSSSVVVVAAAADDEEGGGG",
+        "----------**-----------------------M---------------M------------"),
+    (22, "Scenedesmus obliquus Mitochondrial",
+        "FFLLSS*SYY*LCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        "------*---*---*--------------------M----------------------------"),
+    (23, "Thraustochytrium Mitochondrial",
+        "FF*LSSSSYY**CC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        "--*-------**--*-----------------M--M---------------M------------"),
+    (24, "Rhabdopleuridae Mitochondrial",
+        "FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSSKVVVVAAAADDEEGGGG",
+        "---M------**-------M---------------M---------------M------------"),
+    (25, "Candidate Division SR1 and Gracilibacteria",
+        "FFLLSSSSYY**CCGWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        "---M------**-----------------------M---------------M------------"),
+    (26, "Pachysolen tannophilus Nuclear",
+        "FFLLSSSSYY**CC*WLLLAPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        "----------**--*----M---------------M----------------------------"),
+    (27, "Karyorelict Nuclear",
+        "FFLLSSSSYYQQCCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        "--------------*--------------------M----------------------------"),
+    (28, "Condylostoma Nuclear",
+        "FFLLSSSSYYQQCCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        "----------**--*--------------------M----------------------------"),
+    (29, "Mesodinium Nuclear",
+        "FFLLSSSSYYYYCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        "--------------*--------------------M----------------------------"),
+    (30, "Peritrich Nuclear",
+        "FFLLSSSSYYEECC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        "--------------*--------------------M----------------------------"),
+    (31, "Blastocrithidia Nuclear",
+        "FFLLSSSSYYEECCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        "----------**-----------------------M----------------------------"),
+    (32, "Balanophoraceae Plastid",
+        "FFLLSSSSYY*WCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        "---M------*---*----M------------MMMM---------------M------------"),
+    (33, "Cephalodiscidae Mitochondrial",
+        "FFLLSSSSYYY*CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSSKVVVVAAAADDEEGGGG",
+        "---M-------*-------M---------------M---------------M------------"),
+];
+
+pub struct GeneticCode {
+    pub id: u8,
+    pub name: &'static str,
+    amino_acids: &'static [u8],
+    starts: &'static [u8],
+}
+
+impl GeneticCode {
+    pub fn from_table(id: u8) -> Option<GeneticCode> {
+        TABLES
+            .iter()
+            .find(|table| table.0 == id)
+            .map(|&(id, name, amino_acids, starts)| GeneticCode {
+                id,
+                name,
+                amino_acids: amino_acids.as_bytes(),
+                starts: starts.as_bytes(),
+            })
+    }
+
+    pub fn table_ids() -> Vec<u8> {
+        TABLES.iter().map(|table| table.0).collect()
+    }
+
+    // 确定密码子在TCAG顺序表中的位置；U按T处理
+    pub fn codon_index(codon: &[u8]) -> Option<usize> {
+        if codon.len() != 3 {
+            return None;
+        }
+        let mut index = 0;
+        for &base in codon {
+            let value = match base.to_ascii_uppercase() {
+                b'T' | b'U' => 0,
+                b'C' => 1,
+                b'A' => 2,
+                b'G' => 3,
+                _ => return None,
+            };
+            index = index * 4 + value;
+        }
+        Some(index)
+    }
+
+    // TCAG顺序表中某个位置的密码子，是codon_index的逆运算
+    pub fn codon_at(index: usize) -> [u8; 3] {
+        const BASES: [u8; 4] = [b'T', b'C', b'A', b'G'];
+        [BASES[index / 16], BASES[index / 4 % 4], BASES[index % 4]]
+    }
+
+    // TCAG顺序表中某个位置的氨基酸
+    pub fn amino_acid_at(&self, index: usize) -> u8 {
+        self.amino_acids[index]
+    }
+
+    // 确定密码子对应的氨基酸（终止为'*'）
+    pub fn amino_acid(&self, codon: &[u8]) -> Option<u8> {
+        Self::codon_index(codon).map(|index| self.amino_acids[index])
+    }
+
+    // 该密码表的起始密码子，包括GTG/TTG等替代起始密码子
+    pub fn is_start(&self, codon: &[u8]) -> bool {
+        Self::codon_index(codon).is_some_and(|index| self.starts[index] == b'M')
+    }
+
+    // 总是终止翻译的密码子
+    pub fn is_stop(&self, codon: &[u8]) -> bool {
+        self.amino_acid(codon) == Some(b'*')
+    }
+
+    // 可以作为CDS结尾的密码子：终止密码子，或者只在3'端才终止的密码子
+    // （表27、28和31在起始标记行中用'*'标出）
+    pub fn is_terminal_stop(&self, codon: &[u8]) -> bool {
+        Self::codon_index(codon).is_some_and(|index| self.amino_acids[index] == b'*' || self.starts[index] == b'*')
+    }
+
+    // 可能含有IUPAC简并碱基的密码子对应的氨基酸：
+    // 其代表的所有确定密码子必须翻译成同一个氨基酸，否则为'X'
+    pub fn resolve_amino_acid(&self, codon: &[u8]) -> u8 {
+        if codon.len() != 3 {
+            return b'X';
+        }
+        let mut expansions = Vec::with_capacity(3);
+        for &base in codon {
+            match iupac_bases(base) {
+                Some(bases) => expansions.push(bases),
+                None => return b'X',
+            }
+        }
+
+        let mut resolved = None;
+        for &first in expansions[0] {
+            for &second in expansions[1] {
+                for &third in expansions[2] {
+                    let amino_acid = self.amino_acid(&[first, second, third]);
+                    match resolved {
+                        None => resolved = amino_acid,
+                        Some(previous) if amino_acid != Some(previous) => return b'X',
+                        Some(_) => {}
+                    }
+                }
+            }
+        }
+        resolved.unwrap_or(b'X')
+    }
+
+    // 逐个密码子翻译CDS，末尾不足一个密码子的碱基忽略；
+    // 末尾的终止密码子写成'*'，trim_stop为true时去掉；
+    // start_as_m为true时，5'端的替代起始密码子翻译为M
+    pub fn translate(&self, sequence: &str, trim_stop: bool, start_as_m: bool) -> String {
+        let codons: Vec<&[u8]> = sequence.as_bytes().chunks_exact(3).collect();
+        let mut protein = String::with_capacity(codons.len());
+
+        for (i, codon) in codons.iter().enumerate() {
+            let is_last = i + 1 == codons.len();
+            let amino_acid = self.resolve_amino_acid(codon);
+            if is_last && (amino_acid == b'*' || self.is_terminal_stop(codon)) {
+                if !trim_stop {
+                    protein.push('*');
+                }
+            } else if i == 0 && start_as_m && self.is_start(codon) {
+                protein.push('M');
+            } else {
+                protein.push(amino_acid as char);
+            }
+        }
+
+        protein
+    }
+}
+
+// IUPAC碱基代码所代表的确定碱基
+fn iupac_bases(base: u8) -> Option<&'static [u8]> {
+    let bases: &'static [u8] = match base.to_ascii_uppercase() {
+        b'A' => b"A",
+        b'C' => b"C",
+        b'G' => b"G",
+        b'T' | b'U' => b"T",
+        b'R' => b"AG",
+        b'Y' => b"CT",
+        b'S' => b"CG",
+        b'W' => b"AT",
+        b'K' => b"GT",
+        b'M' => b"AC",
+        b'B' => b"CGT",
+        b'D' => b"AGT",
+        b'H' => b"ACT",
+        b'V' => b"ACG",
+        b'N' => b"ACGT",
+        _ => return None,
+    };
+    Some(bases)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tables_are_well_formed() {
+        let ids = GeneticCode::table_ids();
+        assert_eq!(ids.len(), 27);
+        assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+        for &(id, _, amino_acids, starts) in TABLES.iter() {
+            assert_eq!(amino_acids.len(), 64, "table {}", id);
+            assert_eq!(starts.len(), 64, "table {}", id);
+        }
+        assert!(GeneticCode::from_table(7).is_none());
+        assert!(GeneticCode::from_table(34).is_none());
+    }
+
+    #[test]
+    fn test_codon_index_round_trip() {
+        for index in 0..64 {
+            assert_eq!(GeneticCode::codon_index(&GeneticCode::codon_at(index)), Some(index));
+        }
+        assert_eq!(GeneticCode::codon_index(b"uuu"), Some(0));
+        assert_eq!(GeneticCode::codon_index(b"ANG"), None);
+        assert_eq!(GeneticCode::codon_index(b"AT"), None);
+    }
+
+    #[test]
+    fn test_standard_and_mitochondrial_codes() {
+        let standard = GeneticCode::from_table(1).unwrap();
+        assert!(standard.is_start(b"ATG") && standard.is_start(b"CTG") && !standard.is_start(b"GTG"));
+        assert!(standard.is_stop(b"TGA") && !standard.is_stop(b"TGG"));
+        assert_eq!(standard.amino_acid(b"AGA"), Some(b'R'));
+
+        let vertebrate_mito = GeneticCode::from_table(2).unwrap();
+        assert!(!vertebrate_mito.is_stop(b"TGA"));
+        assert!(vertebrate_mito.is_stop(b"AGA"));
+        assert_eq!(vertebrate_mito.amino_acid(b"ATA"), Some(b'M'));
+    }
+
+    #[test]
+    fn test_balanophoraceae_plastid_code() {
+        let code = GeneticCode::from_table(32).unwrap();
+        assert_eq!(code.name, "Balanophoraceae Plastid");
+        assert_eq!(code.amino_acid(b"TAG"), Some(b'W'));
+        assert!(code.is_stop(b"TAA") && code.is_stop(b"TGA") && !code.is_stop(b"TAG"));
+        for start in [b"TTG", b"CTG", b"ATT", b"ATC", b"ATA", b"ATG", b"GTG"] {
+            assert!(code.is_start(start));
+        }
+    }
+
+    #[test]
+    fn test_terminal_only_stops() {
+        // 表27中TGA只在3'端终止，内部翻译为W
+        let code = GeneticCode::from_table(27).unwrap();
+        assert!(!code.is_stop(b"TGA"));
+        assert!(code.is_terminal_stop(b"TGA"));
+    }
+
+    #[test]
+    fn test_resolve_iupac_codons() {
+        let code = GeneticCode::from_table(1).unwrap();
+        // 第三位简并但翻译结果相同
+        assert_eq!(code.resolve_amino_acid(b"GCN"), b'A');
+        assert_eq!(code.resolve_amino_acid(b"TAR"), b'*');
+        // 翻译结果不同或含有非法字符
+        assert_eq!(code.resolve_amino_acid(b"ATN"), b'X');
+        assert_eq!(code.resolve_amino_acid(b"A-G"), b'X');
+        assert_eq!(code.resolve_amino_acid(b"AT"), b'X');
+    }
+
+    #[test]
+    fn test_translate() {
+        let code = GeneticCode::from_table(1).unwrap();
+        assert_eq!(code.translate("ATGGCNTGGTAA", false, false), "MAW*");
+        assert_eq!(code.translate("ATGGCNTGGTAA", true, false), "MAW");
+        // 内部终止密码子保留为'*'，末尾不足一个密码子的碱基忽略
+        assert_eq!(code.translate("ATGTGAAAAGC", false, false), "M*K");
+        // 替代起始密码子只在start_as_m时翻译为M
+        assert_eq!(code.translate("TTGCTGTAG", false, false), "LL*");
+        assert_eq!(code.translate("TTGCTGTAG", false, true), "ML*");
+        assert_eq!(code.translate("", false, false), "");
+    }
+
+    #[test]
+    fn test_translate_terminal_only_stop() {
+        // 表27中TGA在内部翻译为W，在3'端作为终止
+        let code = GeneticCode::from_table(27).unwrap();
+        assert_eq!(code.translate("ATGTGATGA", false, false), "MW*");
+        assert_eq!(code.translate("ATGTGATGA", true, false), "MW");
+    }
+}
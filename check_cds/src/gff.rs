@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind, Result};
+
+use seq_utils::complement;
+use seq_utils::gff::{is_transcript_type, read_gff};
+
+// 由GFF3文件的CDS行构建的转录本编码模型
+pub struct CdsModel {
+    pub gene_id: String,
+    pub transcript_id: String,
+    pub chrom: String,
+    pub reverse: bool,
+    // CDS片段（从1开始，闭区间），按转录本5'到3'的顺序排列
+    pub blocks: Vec<(usize, usize)>,
+    // 5'端CDS片段的相位：拼接后的CDS开头需要跳过的碱基数（5'不完整的模型）
+    pub phase: usize,
+}
+
+impl CdsModel {
+    pub fn start(&self) -> usize {
+        self.blocks.iter().map(|block| block.0).min().unwrap_or(0)
+    }
+
+    pub fn end(&self) -> usize {
+        self.blocks.iter().map(|block| block.1).max().unwrap_or(0)
+    }
+
+    pub fn strand(&self) -> char {
+        if self.reverse { '-' } else { '+' }
+    }
+
+    // 把CDS中的偏移（从0开始，不含相位跳过的碱基）换算回染色体位置（从1开始）
+    pub fn genomic_position(&self, offset: usize) -> Option<usize> {
+        let mut remaining = offset + self.phase;
+        for &(start, end) in &self.blocks {
+            let length = end - start + 1;
+            if remaining < length {
+                return Some(if self.reverse { end - remaining } else { start + remaining });
+            }
+            remaining -= length;
+        }
+        None
+    }
+
+    // 从基因组中取出CDS片段并拼接，负链模型取反向互补；
+    // 开头按相位跳过不完整密码子的碱基，使序列从第一个完整密码子开始
+    pub fn build_sequence(&self, genome: &HashMap<String, String>) -> Option<String> {
+        let chromosome = genome.get(&self.chrom)?;
+        let mut sequence = String::new();
+        for &(start, end) in &self.blocks {
+            let piece = chromosome.get(start - 1..end)?;
+            if self.reverse {
+                sequence.extend(piece.bytes().rev().map(|base| complement(base) as char));
+            } else {
+                sequence.push_str(piece);
+            }
+        }
+        sequence.drain(..self.phase.min(sequence.len()));
+        Some(sequence)
+    }
+}
+
+// 用seq_utils中共用的解析器读取GFF3（或GTF）文件并构建CDS模型：CDS行按Parent分组，
+// 基因取自mRNA/transcript行的Parent；模型保持输入顺序
+pub fn read_cds_models(filename: &str) -> Result<Vec<CdsModel>> {
+    let mut models: Vec<CdsModel> = Vec::new();
+    let mut model_index: HashMap<String, usize> = HashMap::new();
+    let mut transcript_genes: HashMap<String, String> = HashMap::new();
+
+    for feature in read_gff(filename)? {
+        if is_transcript_type(&feature.feature_type) {
+            if let (Some(id), false) = (&feature.id, feature.parents.is_empty()) {
+                transcript_genes.insert(id.clone(), feature.parents.join(","));
+            }
+            continue;
+        }
+        if feature.feature_type != "CDS" {
+            continue;
+        }
+        if feature.parents.is_empty() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("CDS at {}:{}-{} has no Parent", feature.seqid, feature.start, feature.end),
+            ));
+        }
+        // 共用的解析器保证坐标从1开始且start不大于end
+        let (start, end) = (feature.start as usize, feature.end as usize);
+        let phase = feature.phase.unwrap_or(0);
+        for parent in &feature.parents {
+            let position = *model_index.entry(parent.clone()).or_insert_with(|| {
+                models.push(CdsModel {
+                    gene_id: String::new(),
+                    transcript_id: parent.clone(),
+                    chrom: feature.seqid.clone(),
+                    reverse: feature.reverse,
+                    blocks: Vec::new(),
+                    phase: 0,
+                });
+                models.len() - 1
+            });
+            // 相位取自5'端的片段：正链为起始位置最小的片段，负链为终止位置最大的片段
+            let model = &mut models[position];
+            let five_prime = model.blocks.is_empty()
+                || if model.reverse { end > model.end() } else { start < model.start() };
+            if five_prime {
+                model.phase = phase;
+            }
+            model.blocks.push((start, end));
+        }
+    }
+
+    for model in &mut models {
+        model.gene_id = transcript_genes.get(&model.transcript_id).cloned().unwrap_or_else(|| model.transcript_id.clone());
+        model.blocks.sort_unstable();
+        if model.reverse {
+            model.blocks.reverse();
+        }
+    }
+
+    Ok(models)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_gff(name: &str, content: &str) -> String {
+        let dir = std::env::temp_dir().join(format!("check_cds_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        std::fs::write(&path, content).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    fn genome() -> HashMap<String, String> {
+        //              1234567890123456789012345
+        let chr1 = "GGATGAAACCCTTTGGGTAAGGTTT";
+        HashMap::from([("chr1".to_string(), chr1.to_string())])
+    }
+
+    #[test]
+    fn test_read_models_and_build_sequence() {
+        let path = write_gff("models.gff3", "\
+chr1\tsrc\tmRNA\t3\t20\t.\t+\t.\tID=tx1;Parent=gene1
+chr1\tsrc\tCDS\t13\t20\t.\t+\t0\tID=cds1;Parent=tx1
+chr1\tsrc\tCDS\t3\t8\t.\t+\t0\tID=cds1;Parent=tx1
+chr1\tsrc\tCDS\t3\t8\t.\t-\t0\tID=cds2;Parent=tx2
+");
+        let models = read_cds_models(&path).unwrap();
+        assert_eq!(models.len(), 2);
+        assert_eq!((models[0].gene_id.as_str(), models[0].transcript_id.as_str()), ("gene1", "tx1"));
+        assert_eq!(models[0].blocks, vec![(3, 8), (13, 20)]);
+        assert_eq!((models[0].start(), models[0].end(), models[0].strand()), (3, 20, '+'));
+        assert_eq!(models[0].build_sequence(&genome()).as_deref(), Some("ATGAAATTGGGTAA"));
+        assert_eq!(models[0].genomic_position(6), Some(13));
+        assert_eq!(models[0].genomic_position(14), None);
+
+        // 负链模型取反向互补，位置从3'端往回数
+        assert_eq!(models[1].gene_id, "tx2");
+        assert_eq!(models[1].build_sequence(&genome()).as_deref(), Some("TTTCAT"));
+        assert_eq!(models[1].genomic_position(0), Some(8));
+    }
+
+    #[test]
+    fn test_phase_skips_incomplete_codon() {
+        // 5'不完整的模型：正链看起始位置最小的片段，负链看终止位置最大的片段
+        let path = write_gff("phase.gff3", "\
+chr1\tsrc\tCDS\t13\t20\t.\t+\t.\tParent=tx1
+chr1\tsrc\tCDS\t1\t8\t.\t+\t2\tParent=tx1
+chr1\tsrc\tCDS\t1\t8\t.\t-\t0\tParent=tx2
+chr1\tsrc\tCDS\t13\t20\t.\t-\t1\tParent=tx2
+");
+        let models = read_cds_models(&path).unwrap();
+        assert_eq!(models[0].phase, 2);
+        assert_eq!(models[0].build_sequence(&genome()).as_deref(), Some("ATGAAATTGGGTAA"));
+        assert_eq!(models[0].genomic_position(0), Some(3));
+        assert_eq!(models[1].phase, 1);
+        assert_eq!(models[1].build_sequence(&genome()).as_deref(), Some("TACCCAATTTCATCC"));
+        assert_eq!(models[1].genomic_position(0), Some(19));
+    }
+
+    #[test]
+    fn test_invalid_lines() {
+        let path = write_gff("bad_phase.gff3", "chr1\tsrc\tCDS\t1\t8\t.\t+\t3\tParent=tx1\n");
+        assert!(read_cds_models(&path).is_err());
+        let path = write_gff("no_parent.gff3", "chr1\tsrc\tCDS\t1\t8\t.\t+\t0\tID=cds1\n");
+        assert!(read_cds_models(&path).is_err());
+        let path = write_gff("outside.gff3", "chr1\tsrc\tCDS\t20\t30\t.\t+\t0\tParent=tx1\n");
+        assert!(read_cds_models(&path).unwrap()[0].build_sequence(&genome()).is_none());
+    }
+}
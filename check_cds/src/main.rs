@@ -1,9 +1,19 @@
+mod codon_usage;
+mod fix;
+mod frames;
+mod genetic_code;
+mod gff;
+
 use clap::{App, Arg};
+use seq_utils::open_input;
+use rayon::prelude::*;
 use std::collections::HashMap;
 use std::fs::File;
-use std::io::{BufRead, BufReader, Result};
+use std::io::{BufRead, Error, Result};
 use std::io::Write;
 
+use genetic_code::GeneticCode;
+
 struct CdsSequence {
     id: String,
     sequence: String,
@@ -17,11 +27,27 @@ struct ErrorReport {
     premature_stop_codon: bool,
 }
 
-type ReportTable = HashMap<String, ErrorReport>;
+// 按输入顺序保存，同一输入重复运行得到完全相同的报告
+type ReportTable = Vec<(String, ErrorReport)>;
+
+// 基因组模式报告中的一行；染色体位置均从1开始
+struct GenomeReportRow<'a> {
+    model: &'a gff::CdsModel,
+    // CDS无法从基因组中取出时为None，原因见build_error
+    report: Option<ErrorReport>,
+    build_error: Option<String>,
+    // 缺少起始密码子时，第一个密码子第一个碱基的位置
+    missing_start_position: Option<usize>,
+    // 缺少终止密码子时，最后三个碱基中第一个碱基的位置
+    missing_stop_position: Option<usize>,
+    // 长度不是3的倍数时，末尾不完整密码子第一个碱基的位置
+    frame_error_position: Option<usize>,
+    // 每个提前终止密码子第一个碱基的位置
+    premature_stop_positions: Vec<usize>,
+}
 
 fn read_cds_file(filename: &str) -> Result<Vec<CdsSequence>> {
-    let file = File::open(filename)?;
-    let reader = BufReader::new(file);
+    let reader = open_input(filename)?;
     let mut cds_sequences = Vec::new();
     let mut current_id = String::new();
     let mut current_sequence = String::new();
@@ -81,82 +107,363 @@ fn read_cds_file(filename: &str) -> Result<Vec<CdsSequence>> {
 //     false
 // }
 
-fn check_for_errors(sequence: &str) -> ErrorReport {
-    let mut error_report = ErrorReport {
-        missing_start_codon: false,
-        missing_stop_codon: false,
-        illegal_codon_sequence: false,
-        non_multiple_of_three_length: false,
-        premature_stop_codon: false,
-    };
-
-    // Convert sequence to uppercase to ensure consistency
-    let sequence = sequence.to_uppercase();
+// 直接在字节上检查，每个密码子不做任何内存分配
+fn check_for_errors(sequence: &str, code: &GeneticCode) -> ErrorReport {
+    let bases = sequence.as_bytes();
 
     // Check for missing start codon
-    if !sequence.starts_with("ATG") {
-        error_report.missing_start_codon = true;
-    }
+    let missing_start_codon = !bases.get(..3).is_some_and(|codon| code.is_start(codon));
     // Check for missing stop codon
-    if !sequence.ends_with("TAA") && !sequence.ends_with("TAG") && !sequence.ends_with("TGA") {
-        error_report.missing_stop_codon = true;
-    }
+    let last_codon = bases.len().checked_sub(3).map(|start| &bases[start..]);
+    let missing_stop_codon = !last_codon.is_some_and(|codon| code.is_terminal_stop(codon));
     // Check for illegal codon sequence
-    if sequence.chars().any(|c| !"ATCG".contains(c)) {
-        error_report.illegal_codon_sequence = true;
-    }
+    let illegal_codon_sequence = bases
+        .iter()
+        .any(|base| !matches!(base.to_ascii_uppercase(), b'A' | b'C' | b'G' | b'T'));
     // Check for non-multiple of three length
-    if sequence.len() % 3 != 0 {
-        error_report.non_multiple_of_three_length = true;
-    }
+    let non_multiple_of_three_length = bases.len() % 3 != 0;
     // Check for premature stop codon
-    let stop_codons = ["TAA", "TAG", "TGA"];
-    let codon_length = 3;
-    let substrings = sequence
-        .chars()
-        .collect::<Vec<_>>()
-        .chunks(codon_length)
-        .map(|chunk| chunk.iter().collect::<String>())
-        .collect::<Vec<_>>();
-
-    // Remove the last substring
-    let substrings_without_last = if substrings.len() > 1 {
-        &substrings[..substrings.len() - 1]
-    } else {
-        &[]
-    };
-
-    // Check for premature stop codon in each substring
-    let has_stop_codon = substrings_without_last.iter().any(|substring| {
-        stop_codons
-            .iter()
-            .any(|&stop| substring == stop)
-    });
-
-    error_report.premature_stop_codon = has_stop_codon;
-
-    error_report
-}
-
-fn generate_report_table(cds_sequences: &[CdsSequence]) -> ReportTable {
-    let mut report_table = ReportTable::new();
-
-    for sequence in cds_sequences {
-        let error_report = check_for_errors(&sequence.sequence);
-        // Only insert into report_table if errors are found
-        if error_report.missing_start_codon
-            || error_report.missing_stop_codon
-            || error_report.illegal_codon_sequence
-            || error_report.non_multiple_of_three_length 
-            || error_report.premature_stop_codon {
-            report_table.insert(sequence.id.clone(), error_report);
+    let premature_stop_codon = interior_codons(bases).any(|codon| code.is_stop(codon));
+
+    ErrorReport {
+        missing_start_codon,
+        missing_stop_codon,
+        illegal_codon_sequence,
+        non_multiple_of_three_length,
+        premature_stop_codon,
+    }
+}
+
+// 序列最后一个（可能不完整的）密码子之前的所有完整密码子
+fn interior_codons(bases: &[u8]) -> std::slice::ChunksExact<'_, u8> {
+    let interior = bases.len().saturating_sub(1) / 3 * 3;
+    bases[..interior].chunks_exact(3)
+}
+
+// 读码框内终止密码子的偏移（从0开始），不含最后一个密码子
+fn find_premature_stops(sequence: &str, code: &GeneticCode) -> Vec<usize> {
+    interior_codons(sequence.as_bytes())
+        .enumerate()
+        .filter(|(_, codon)| code.is_stop(codon))
+        .map(|(index, _)| index * 3)
+        .collect()
+}
+
+fn has_errors(error_report: &ErrorReport) -> bool {
+    error_report.missing_start_codon
+        || error_report.missing_stop_codon
+        || error_report.illegal_codon_sequence
+        || error_report.non_multiple_of_three_length
+        || error_report.premature_stop_codon
+}
+
+// 读取基因组FASTA，以标题行的第一个词为键
+fn read_genome(filename: &str) -> Result<HashMap<String, String>> {
+    Ok(read_cds_file(filename)?
+        .into_iter()
+        .map(|record| {
+            let id = record.id.split_whitespace().next().unwrap_or("").to_string();
+            (id, record.sequence)
+        })
+        .collect())
+}
+
+// 从基因组中拼接出每个模型的CDS，以转录本ID命名；
+// 单个模型无法拼接（序列不存在或坐标越界）时记录原因，不影响其他模型
+fn build_genome_cds(models: &[gff::CdsModel], genome: &HashMap<String, String>) -> Vec<std::result::Result<CdsSequence, String>> {
+    models
+        .par_iter()
+        .map(|model| {
+            if !genome.contains_key(&model.chrom) {
+                return Err(format!("sequence {} not found in the genome", model.chrom));
+            }
+            let sequence = model.build_sequence(genome)
+                .ok_or_else(|| format!("CDS lies outside sequence {} in the genome", model.chrom))?;
+            Ok(CdsSequence { id: model.transcript_id.clone(), sequence })
+        })
+        .collect()
+}
+
+fn generate_genome_report<'a>(
+    models: &'a [gff::CdsModel],
+    cds_sequences: &[std::result::Result<CdsSequence, String>],
+    code: &GeneticCode,
+    include_passing: bool,
+) -> Vec<GenomeReportRow<'a>> {
+    // 带索引的并行迭代器按输入顺序收集结果
+    models
+        .par_iter()
+        .zip(cds_sequences)
+        .filter_map(|(model, cds)| {
+            let cds = match cds {
+                Ok(cds) => cds,
+                Err(message) => {
+                    return Some(GenomeReportRow {
+                        model,
+                        report: None,
+                        build_error: Some(message.clone()),
+                        missing_start_position: None,
+                        missing_stop_position: None,
+                        frame_error_position: None,
+                        premature_stop_positions: Vec::new(),
+                    });
+                }
+            };
+            let report = check_for_errors(&cds.sequence, code);
+            if !include_passing && !has_errors(&report) {
+                return None;
+            }
+            let length = cds.sequence.len();
+            let position_if = |flag: bool, offset: Option<usize>| {
+                offset.filter(|_| flag).and_then(|offset| model.genomic_position(offset))
+            };
+            let missing_start_position = position_if(report.missing_start_codon, (length > 0).then_some(0));
+            let missing_stop_position = position_if(report.missing_stop_codon, length.checked_sub(3));
+            let frame_error_position = position_if(report.non_multiple_of_three_length, Some(length / 3 * 3));
+            let premature_stop_positions = find_premature_stops(&cds.sequence, code)
+                .into_iter()
+                .filter_map(|offset| model.genomic_position(offset))
+                .collect();
+            Some(GenomeReportRow {
+                model,
+                report: Some(report),
+                build_error: None,
+                missing_start_position,
+                missing_stop_position,
+                frame_error_position,
+                premature_stop_positions,
+            })
+        })
+        .collect()
+}
+
+fn write_genome_report_to_file(rows: &[GenomeReportRow], summarize: bool, filename: &str) -> Result<()> {
+    let mut file = File::create(filename)?;
+    writeln!(file, "Gene_ID\tTranscript_ID\tChrom\tStart\tEnd\tStrand\tMissing_Start_Codon\tMissing_Stop_Codon\tIllegal_Codon_Sequence\tNon_Multiple_Of_Three_Length\tPremature_stop_codon\tPremature_Stop_Positions\tMissing_Start_Position\tMissing_Stop_Position\tFrame_Error_Position\tBuild_Error")?;
+
+    let position = |position: Option<usize>| position.map_or(".".to_string(), |p| p.to_string());
+    for row in rows {
+        let positions = if row.premature_stop_positions.is_empty() {
+            ".".to_string()
+        } else {
+            row.premature_stop_positions.iter().map(|p| p.to_string()).collect::<Vec<_>>().join(",")
+        };
+        // 无法拼接的模型没有检查结果，各项标记写成"."
+        let flags = match &row.report {
+            Some(report) => [
+                report.missing_start_codon,
+                report.missing_stop_codon,
+                report.illegal_codon_sequence,
+                report.non_multiple_of_three_length,
+                report.premature_stop_codon,
+            ].map(|flag| if flag { "1" } else { "0" }),
+            None => ["."; 5],
+        };
+        writeln!(file, "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            row.model.gene_id, row.model.transcript_id, row.model.chrom,
+            row.model.start(), row.model.end(), row.model.strand(),
+            flags[0], flags[1], flags[2], flags[3], flags[4],
+            positions,
+            position(row.missing_start_position),
+            position(row.missing_stop_position),
+            position(row.frame_error_position),
+            row.build_error.as_deref().unwrap_or(".")
+        )?;
+    }
+
+    if summarize {
+        write_summary_footer(&mut file, rows.iter().filter_map(|row| row.report.as_ref()))?;
+        let unbuilt = rows.iter().filter(|row| row.report.is_none()).count();
+        writeln!(file, "# Build_Error\t{}", unbuilt)?;
+    }
+
+    Ok(())
+}
+
+fn generate_report_table(cds_sequences: &[CdsSequence], code: &GeneticCode, include_passing: bool) -> ReportTable {
+    // 并行检查各条序列，收集时保持输入顺序
+    cds_sequences
+        .par_iter()
+        .filter_map(|sequence| {
+            let error_report = check_for_errors(&sequence.sequence, code);
+            // Only insert into report_table if errors are found, unless the full report was requested
+            if include_passing || has_errors(&error_report) {
+                Some((sequence.id.clone(), error_report))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+// 长格式TSV，给出每条有错误的序列中各个错误的位置；
+// 位置为CDS内从1开始的坐标，密码子从5'端开始编号
+fn write_error_details_to_file(cds_sequences: &[CdsSequence], code: &GeneticCode, filename: &str) -> Result<()> {
+    let mut file = std::io::BufWriter::new(File::create(filename)?);
+    writeln!(file, "Sequence_ID\tField\tPosition\tCodon\tValue")?;
+
+    for cds in cds_sequences {
+        let report = check_for_errors(&cds.sequence, code);
+        if !has_errors(&report) {
+            continue;
+        }
+        let id = &cds.id;
+        let bases = cds.sequence.as_bytes();
+        let remainder = bases.len() % 3;
+
+        let first_codon = String::from_utf8_lossy(&bases[..bases.len().min(3)]);
+        writeln!(file, "{}\tfirst_codon\t1\t1\t{}", id, first_codon)?;
+        if bases.len() >= 3 {
+            let last_start = bases.len() - 3;
+            let last_number = if remainder == 0 { (last_start / 3 + 1).to_string() } else { ".".to_string() };
+            let last_codon = String::from_utf8_lossy(&bases[last_start..]);
+            writeln!(file, "{}\tlast_codon\t{}\t{}\t{}", id, last_start + 1, last_number, last_codon)?;
+        }
+        writeln!(file, "{}\tlength_mod_3\t.\t.\t{}", id, remainder)?;
+
+        let premature_stops = find_premature_stops(&cds.sequence, code);
+        for &offset in &premature_stops {
+            let codon = String::from_utf8_lossy(&bases[offset..offset + 3]);
+            writeln!(file, "{}\tpremature_stop\t{}\t{}\t{}", id, offset + 1, offset / 3 + 1, codon)?;
+        }
+
+        let mut illegal_count = 0;
+        for (offset, &base) in bases.iter().enumerate() {
+            if !b"ATCG".contains(&base.to_ascii_uppercase()) {
+                illegal_count += 1;
+                writeln!(file, "{}\tillegal_base\t{}\t{}\t{}", id, offset + 1, offset / 3 + 1, base as char)?;
+            }
+        }
+
+        let counts = [
+            ("missing_start_codon", report.missing_start_codon as usize),
+            ("missing_stop_codon", report.missing_stop_codon as usize),
+            ("illegal_base", illegal_count),
+            ("non_multiple_of_three_length", report.non_multiple_of_three_length as usize),
+            ("premature_stop_codon", premature_stops.len()),
+        ];
+        for (error_type, count) in counts {
+            writeln!(file, "{}\t{}_count\t.\t.\t{}", id, error_type, count)?;
         }
     }
 
-    report_table
+    file.flush()
 }
 
-fn write_report_table_to_file(report_table: &ReportTable, filename: &str) -> Result<()> {
+// 输出修复后的CDS，以及每处修改一行的TSV日志
+fn write_fixed_files(
+    cds_sequences: &[CdsSequence],
+    code: &GeneticCode,
+    options: &fix::FixOptions,
+    fasta_filename: &str,
+    log_filename: &str,
+) -> Result<()> {
+    let mut fasta = std::io::BufWriter::new(File::create(fasta_filename)?);
+    let mut log = std::io::BufWriter::new(File::create(log_filename)?);
+    writeln!(log, "Sequence_ID\tEdit\tDetail")?;
+
+    for cds in cds_sequences {
+        let result = fix::fix_sequence(&cds.sequence, code, options);
+        for (edit, detail) in &result.edits {
+            writeln!(log, "{}\t{}\t{}", cds.id, edit, detail)?;
+        }
+        if let Some(sequence) = result.sequence {
+            writeln!(fasta, ">{}", cds.id)?;
+            writeln!(fasta, "{}", sequence)?;
+        }
+    }
+
+    fasta.flush()?;
+    log.flush()
+}
+
+// 按输入顺序输出每条序列的密码子使用情况，
+// 最后以Sequence_ID "*"输出整个文件的汇总
+fn write_codon_usage_to_file(cds_sequences: &[CdsSequence], code: &GeneticCode, filename: &str) -> Result<()> {
+    let mut file = std::io::BufWriter::new(File::create(filename)?);
+    writeln!(file, "Sequence_ID\tCodon\tAmino_Acid\tCount\tPer_Thousand\tRSCU")?;
+
+    let mut totals = codon_usage::CodonCounts::default();
+    for cds in cds_sequences {
+        let counts = codon_usage::CodonCounts::from_sequence(&cds.sequence);
+        write_codon_usage_rows(&mut file, &cds.id, &counts, code)?;
+        totals.add(&counts);
+    }
+    write_codon_usage_rows(&mut file, "*", &totals, code)?;
+
+    file.flush()
+}
+
+fn write_codon_usage_rows(
+    file: &mut impl Write,
+    id: &str,
+    counts: &codon_usage::CodonCounts,
+    code: &GeneticCode,
+) -> Result<()> {
+    for row in counts.usage(code) {
+        let rscu = match row.rscu {
+            Some(rscu) => format!("{:.3}", rscu),
+            None => ".".to_string(),
+        };
+        writeln!(file, "{}\t{}\t{}\t{}\t{:.2}\t{}",
+            id,
+            String::from_utf8_lossy(&row.codon),
+            row.amino_acid as char,
+            row.count,
+            row.per_thousand,
+            rscu
+        )?;
+    }
+
+    Ok(())
+}
+
+// 对每条有错误的序列，比较六个读码框的最长ORF和注释读码框，
+// 给出看起来正确的读码框和链
+fn write_frame_report_to_file(cds_sequences: &[CdsSequence], code: &GeneticCode, filename: &str) -> Result<()> {
+    let mut file = std::io::BufWriter::new(File::create(filename)?);
+    writeln!(file, "Sequence_ID\tAnnotated_ORF_Codons\tBest_Frame\tBest_Strand\tBest_ORF_Codons\tBest_ORF_Start\tBest_ORF_End\tDiagnosis")?;
+
+    for cds in cds_sequences {
+        let report = check_for_errors(&cds.sequence, code);
+        if !has_errors(&report) {
+            continue;
+        }
+        let orfs = frames::scan_frames(&cds.sequence, code);
+        let best = frames::best_frame(&orfs);
+        writeln!(file, "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            cds.id,
+            orfs[0].codons,
+            best.frame(),
+            best.strand(),
+            best.codons,
+            best.start,
+            best.end,
+            best.diagnosis()
+        )?;
+    }
+
+    file.flush()
+}
+
+fn write_protein_file(
+    cds_sequences: &[CdsSequence],
+    code: &GeneticCode,
+    trim_stop: bool,
+    start_as_m: bool,
+    filename: &str,
+) -> Result<()> {
+    let mut file = std::io::BufWriter::new(File::create(filename)?);
+
+    for cds in cds_sequences {
+        writeln!(file, ">{}", cds.id)?;
+        writeln!(file, "{}", code.translate(&cds.sequence, trim_stop, start_as_m))?;
+    }
+
+    file.flush()
+}
+
+fn write_report_table_to_file(report_table: &ReportTable, summarize: bool, filename: &str) -> Result<()> {
     let mut file = File::create(filename)?;
     writeln!(file, "Sequence_ID\tMissing_Start_Codon\tMissing_Stop_Codon\tIllegal_Codon_Sequence\tNon_Multiple_Of_Three_Length]\tPremature_stop_codon")?;
     
@@ -170,6 +477,50 @@ fn write_report_table_to_file(report_table: &ReportTable, filename: &str) -> Res
         )?;
     }
 
+    if summarize {
+        write_summary_footer(&mut file, report_table.iter().map(|(_, report)| report))?;
+    }
+
+    Ok(())
+}
+
+// 报告末尾的汇总：检查、通过和未通过的序列数，以及每种错误的数量
+fn write_summary_footer<'a>(file: &mut File, reports: impl Iterator<Item = &'a ErrorReport>) -> Result<()> {
+    let mut total = 0;
+    let mut passing = 0;
+    let mut counts = [0usize; 5];
+
+    for report in reports {
+        total += 1;
+        if !has_errors(report) {
+            passing += 1;
+        }
+        let flags = [
+            report.missing_start_codon,
+            report.missing_stop_codon,
+            report.illegal_codon_sequence,
+            report.non_multiple_of_three_length,
+            report.premature_stop_codon,
+        ];
+        for (count, flag) in counts.iter_mut().zip(flags) {
+            *count += flag as usize;
+        }
+    }
+
+    writeln!(file, "# Total_Sequences\t{}", total)?;
+    writeln!(file, "# Passing\t{}", passing)?;
+    writeln!(file, "# Failing\t{}", total - passing)?;
+    let names = [
+        "Missing_Start_Codon",
+        "Missing_Stop_Codon",
+        "Illegal_Codon_Sequence",
+        "Non_Multiple_Of_Three_Length",
+        "Premature_stop_codon",
+    ];
+    for (name, count) in names.iter().zip(counts) {
+        writeln!(file, "# {}\t{}", name, count)?;
+    }
+
     Ok(())
 }
 
@@ -184,7 +535,91 @@ fn main() -> Result<()> {
             .value_name("FILE")
             .help("Sets the input file to use")
             .takes_value(true)
-            .required(true))
+            .required_unless_present("genome")
+            .conflicts_with("genome"))
+        .arg(Arg::new("genome")
+            .short('g')
+            .long("genome")
+            .value_name("FILE")
+            .help("Genome FASTA; builds each transcript's CDS from --gff instead of reading --input")
+            .takes_value(true)
+            .requires("gff"))
+        .arg(Arg::new("gff")
+            .long("gff")
+            .value_name("FILE")
+            .help("GFF3 annotation with CDS features, used with --genome")
+            .takes_value(true)
+            .requires("genome"))
+        .arg(Arg::new("table")
+            .short('t')
+            .long("table")
+            .value_name("N")
+            .help("NCBI genetic code table used for start and stop codons")
+            .takes_value(true)
+            .default_value("1"))
+        .arg(Arg::new("all")
+            .short('a')
+            .long("all")
+            .help("Lists passing sequences too (all flags 0) and appends a summary footer"))
+        .arg(Arg::new("details")
+            .short('d')
+            .long("details")
+            .value_name("FILE")
+            .help("Also writes a long-format TSV with the position of every error in flagged sequences")
+            .takes_value(true))
+        .arg(Arg::new("fix")
+            .long("fix")
+            .value_name("FILE")
+            .help("Writes repaired CDS sequences: truncated at the first in-frame stop and trimmed to whole codons")
+            .takes_value(true))
+        .arg(Arg::new("fix_log")
+            .long("fix-log")
+            .value_name("FILE")
+            .help("Log of every edit made by --fix [default: <fix FILE>.log]")
+            .takes_value(true)
+            .requires("fix"))
+        .arg(Arg::new("illegal")
+            .long("illegal")
+            .value_name("ACTION")
+            .help("What --fix does with sequences containing illegal characters")
+            .takes_value(true)
+            .possible_values(["mask", "drop"])
+            .default_value("mask"))
+        .arg(Arg::new("trim_to_start")
+            .long("trim-to-start")
+            .help("Makes --fix also trim each sequence to its first in-frame start codon")
+            .requires("fix"))
+        .arg(Arg::new("codon_usage")
+            .short('c')
+            .long("codon-usage")
+            .value_name("FILE")
+            .help("Writes codon counts, frequency per thousand and RSCU per sequence and for the whole input")
+            .takes_value(true))
+        .arg(Arg::new("frames")
+            .long("frames")
+            .value_name("FILE")
+            .help("Writes the frame and strand with the longest ORF for each flagged sequence")
+            .takes_value(true))
+        .arg(Arg::new("protein")
+            .short('p')
+            .long("protein")
+            .value_name("FILE")
+            .help("Also writes the translation of every CDS to this protein FASTA")
+            .takes_value(true))
+        .arg(Arg::new("trim_stop")
+            .long("trim-stop")
+            .help("Leaves the terminal stop ('*') out of --protein translations")
+            .requires("protein"))
+        .arg(Arg::new("start_as_m")
+            .long("start-as-m")
+            .help("Translates an alternative start codon at the 5' end as M in --protein output")
+            .requires("protein"))
+        .arg(Arg::new("threads")
+            .long("threads")
+            .value_name("N")
+            .help("Number of threads used to check sequences; 0 uses every core")
+            .takes_value(true)
+            .default_value("0"))
         .arg(Arg::new("output")
             .short('o')
             .long("output")
@@ -196,14 +631,86 @@ fn main() -> Result<()> {
 
     match matches {
         Ok(matches) => {
-            let cds_filename = matches.value_of("input").unwrap();
             let output_filename = matches.value_of("output").unwrap();
+            let table = matches.value_of("table").unwrap();
+            let code = match table.parse().ok().and_then(GeneticCode::from_table) {
+                Some(code) => code,
+                None => {
+                    let available: Vec<String> = GeneticCode::table_ids().iter().map(|id| id.to_string()).collect();
+                    eprintln!("Unknown genetic code table {}; available tables: {}", table, available.join(", "));
+                    std::process::exit(1);
+                }
+            };
+
+            let threads: usize = match matches.value_of("threads").unwrap().parse() {
+                Ok(threads) => threads,
+                Err(_) => {
+                    eprintln!("--threads expects a non-negative integer");
+                    std::process::exit(1);
+                }
+            };
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build_global()
+                .map_err(Error::other)?;
 
-            let cds_sequences = read_cds_file(cds_filename)?;
-            let report_table = generate_report_table(&cds_sequences);
-            write_report_table_to_file(&report_table, output_filename)?;
+            let include_all = matches.is_present("all");
+            let cds_sequences;
+            if let Some(genome_filename) = matches.value_of("genome") {
+                let gff_filename = matches.value_of("gff").unwrap();
+                let genome = read_genome(genome_filename)?;
+                let models = gff::read_cds_models(gff_filename)?;
+                let built = build_genome_cds(&models, &genome);
+                let rows = generate_genome_report(&models, &built, &code, include_all);
+                write_genome_report_to_file(&rows, include_all, output_filename)?;
+                let unbuilt = rows.iter().filter(|row| row.report.is_none()).count();
+                if unbuilt > 0 {
+                    eprintln!("{} CDS models could not be built from the genome; see Build_Error in the report", unbuilt);
+                }
+                // 其余输出只使用成功拼接的CDS
+                cds_sequences = built.into_iter().filter_map(|cds| cds.ok()).collect::<Vec<_>>();
+            } else {
+                let cds_filename = matches.value_of("input").unwrap();
+                cds_sequences = read_cds_file(cds_filename)?;
+                let report_table = generate_report_table(&cds_sequences, &code, include_all);
+                write_report_table_to_file(&report_table, include_all, output_filename)?;
+            }
 
-            println!("Error report generated successfully.");
+            if let Some(details_filename) = matches.value_of("details") {
+                write_error_details_to_file(&cds_sequences, &code, details_filename)?;
+            }
+
+            if let Some(fix_filename) = matches.value_of("fix") {
+                let log_filename = match matches.value_of("fix_log") {
+                    Some(name) => name.to_string(),
+                    None => format!("{}.log", fix_filename),
+                };
+                let options = fix::FixOptions {
+                    illegal: fix::IllegalAction::from_name(matches.value_of("illegal").unwrap()).unwrap(),
+                    trim_to_start: matches.is_present("trim_to_start"),
+                };
+                write_fixed_files(&cds_sequences, &code, &options, fix_filename, &log_filename)?;
+            }
+
+            if let Some(usage_filename) = matches.value_of("codon_usage") {
+                write_codon_usage_to_file(&cds_sequences, &code, usage_filename)?;
+            }
+
+            if let Some(frames_filename) = matches.value_of("frames") {
+                write_frame_report_to_file(&cds_sequences, &code, frames_filename)?;
+            }
+
+            if let Some(protein_filename) = matches.value_of("protein") {
+                write_protein_file(
+                    &cds_sequences,
+                    &code,
+                    matches.is_present("trim_stop"),
+                    matches.is_present("start_as_m"),
+                    protein_filename,
+                )?;
+            }
+
+            println!("Error report generated successfully with genetic code table {} ({}).", code.id, code.name);
         }
         Err(err) => {
             eprintln!("{}", err);
@@ -211,4 +718,72 @@ fn main() -> Result<()> {
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn model(transcript_id: &str, chrom: &str, reverse: bool, blocks: Vec<(usize, usize)>) -> gff::CdsModel {
+        gff::CdsModel {
+            gene_id: transcript_id.to_string(),
+            transcript_id: transcript_id.to_string(),
+            chrom: chrom.to_string(),
+            reverse,
+            blocks,
+            phase: 0,
+        }
+    }
+
+    #[test]
+    fn test_check_for_errors() {
+        let code = GeneticCode::from_table(1).unwrap();
+        let report = check_for_errors("ATGAAATAA", &code);
+        assert!(!has_errors(&report));
+
+        let report = check_for_errors("CCGTAAGNAT", &code);
+        assert!(report.missing_start_codon);
+        assert!(report.missing_stop_codon);
+        assert!(report.illegal_codon_sequence);
+        assert!(report.non_multiple_of_three_length);
+        assert!(report.premature_stop_codon);
+        assert_eq!(find_premature_stops("ATGTAATGATAG", &code), vec![3, 6]);
+    }
+
+    #[test]
+    fn test_genome_report_rows() {
+        let code = GeneticCode::from_table(1).unwrap();
+        //                    1234567890123456789012345
+        let chr1 = "GGATGAAACCCTTTGGGTAAGGTTT";
+        let genome = HashMap::from([("chr1".to_string(), chr1.to_string())]);
+        let models = vec![
+            model("complete", "chr1", false, vec![(3, 8), (15, 20)]),
+            model("missing", "chr2", false, vec![(1, 9)]),
+            model("outside", "chr1", false, vec![(20, 40)]),
+            model("broken", "chr1", true, vec![(10, 20)]),
+            model("stop", "chr1", false, vec![(3, 5), (18, 23)]),
+        ];
+
+        // 无法拼接的模型记录原因，其他模型照常检查
+        let built = build_genome_cds(&models, &genome);
+        assert!(built[0].is_ok());
+        assert!(built[1].as_ref().err().unwrap().contains("not found"));
+        assert!(built[2].as_ref().err().unwrap().contains("outside"));
+
+        let rows = generate_genome_report(&models, &built, &code, false);
+        let ids: Vec<&str> = rows.iter().map(|row| row.model.transcript_id.as_str()).collect();
+        assert_eq!(ids, vec!["missing", "outside", "broken", "stop"]);
+        assert!(rows[0].report.is_none());
+
+        // 起始、终止和读码框错误都换算成染色体位置
+        let broken = &rows[2];
+        assert_eq!(broken.missing_start_position, Some(20));
+        assert_eq!(broken.missing_stop_position, Some(12));
+        assert_eq!(broken.frame_error_position, Some(11));
+
+        let stop = &rows[3];
+        assert_eq!(stop.premature_stop_positions, vec![18]);
+        assert_eq!(stop.missing_start_position, None);
+        assert_eq!(stop.missing_stop_position, Some(21));
+    }
+}
@@ -1,24 +1,35 @@
+use seq_utils::open_input;
 use std::env;
 use std::fs::File;
-use std::io::{BufReader, BufRead, Write};
+use std::io::{BufRead, Write};
 
 fn main() {
     // 获取命令行参数
     let args: Vec<String> = env::args().collect();
     if args.len() != 3 {
-        eprintln!("Usage: {} <input_fa_file> <output_file>", args[0]);
+        eprintln!("Usage: {} <input_fa_or_fq_file> <output_file>", args[0]);
         std::process::exit(1);
     }
     let input_file_path = &args[1];
     let output_file_path = &args[2];
 
-    // 打开输入文件
-    let file = File::open(input_file_path).expect("Failed to open input file");
-    let reader = BufReader::new(file);
+    // 打开输入文件，根据魔数自动识别gzip/bgzip压缩
+    let mut reader = open_input(input_file_path).expect("Failed to open input file");
 
     // 打开输出文件，使用写入模式来覆盖已存在的文件
     let mut output_file = File::create(output_file_path).expect("Failed to create output file");
 
+    // 根据第一个字符判断输入是FASTA还是FASTQ
+    let is_fastq = reader.fill_buf().expect("Failed to read input file").first() == Some(&b'@');
+    if is_fastq {
+        write_fastq_sizes(reader, &mut output_file);
+    } else {
+        write_fasta_sizes(reader, &mut output_file);
+    }
+}
+
+// 逐行读取FASTA文件，输出每条序列的名称和长度
+fn write_fasta_sizes(reader: Box<dyn BufRead>, output_file: &mut File) {
     // 定义变量来跟踪当前序列的名称
     let mut current_sequence_name = String::new();
     let mut current_sequence_length = 0;
@@ -28,14 +39,14 @@ fn main() {
         let line = line.expect("Failed to read line");
 
         // 如果行以 ">" 开头，表示新的序列开始
-        if line.starts_with('>') {
+        if let Some(header) = line.strip_prefix('>') {
             // 处理前一个序列（如果有的话）
             if !current_sequence_name.is_empty() {
                 // 输出当前序列的名称和长度到输出文件中，用 tab 分隔
                 writeln!(output_file, "{}\t{}", current_sequence_name, current_sequence_length).expect("Failed to write output");
             }
             // 更新当前序列的名称，同时重置当前序列的长度
-            current_sequence_name = line[1..].trim().split_whitespace().next().unwrap_or("").to_string();
+            current_sequence_name = header.split_whitespace().next().unwrap_or("").to_string();
             current_sequence_length = 0;
         } else {
             // 如果不是以 ">" 开头，则将行的长度添加到当前序列的长度中
@@ -49,3 +60,38 @@ fn main() {
         writeln!(output_file, "{}\t{}", current_sequence_name, current_sequence_length).expect("Failed to write output");
     }
 }
+
+// 逐条读取FASTQ记录，输出每条read的名称和长度
+fn write_fastq_sizes(reader: Box<dyn BufRead>, output_file: &mut File) {
+    let mut lines = reader.lines();
+
+    while let Some(line) = lines.next() {
+        let line = line.expect("Failed to read line");
+        // 跳过记录之间的空行
+        if line.trim().is_empty() {
+            continue;
+        }
+        let header = line.strip_prefix('@').expect("Invalid FASTQ header");
+        let read_name = header.split_whitespace().next().unwrap_or("").to_string();
+
+        // 序列行一直到 "+" 分隔行为止，支持多行FASTQ
+        let mut read_length = 0;
+        loop {
+            let line = lines.next().expect("Truncated FASTQ record").expect("Failed to read line");
+            if line.starts_with('+') {
+                break;
+            }
+            read_length += line.trim().len();
+        }
+
+        // 跳过与序列等长的质量行
+        let mut quality_length = 0;
+        while quality_length < read_length {
+            let line = lines.next().expect("Truncated FASTQ record").expect("Failed to read line");
+            quality_length += line.trim().len();
+        }
+
+        // 输出read的名称和长度，用 tab 分隔
+        writeln!(output_file, "{}\t{}", read_name, read_length).expect("Failed to write output");
+    }
+}
@@ -0,0 +1,303 @@
+use crate::compress::open_input;
+use crate::split::{FragmentStatus, SplitPosition};
+use std::collections::HashSet;
+use std::io::{self, BufRead, Write};
+
+/// AGP 2.1允许的gap类型
+const GAP_TYPES: [&str; 8] = [
+    "scaffold", "contig", "centromere", "short_arm", "heterochromatin", "telomere", "repeat", "contamination",
+];
+
+/// AGP 2.1允许的连接证据
+const LINKAGE_EVIDENCE: [&str; 12] = [
+    "na", "paired-ends", "align_genus", "align_xgenus", "align_trnscpt", "within_clone", "clone_contig", "map",
+    "pcr", "proximity_ligation", "strobe", "unspecified",
+];
+
+/// 输出AGP文件时gap行的参数
+pub struct AgpOptions {
+    pub gap_type: String,                  // gap类型（第7列）
+    pub linkage: bool,                     // gap两侧是否有连接证据（第8列）
+    pub linkage_evidence: String,          // 连接证据，多个用';'分隔（第9列）
+    pub unknown_gap_length: Option<usize>, // 该长度的gap写成U行（未知长度）
+}
+
+impl AgpOptions {
+    /// 按AGP 2.1规范检查gap参数的组合是否合法
+    pub fn validate(&self) -> Result<(), String> {
+        if !GAP_TYPES.contains(&self.gap_type.as_str()) {
+            return Err(format!("Invalid AGP gap type: {}", self.gap_type));
+        }
+        let evidence: Vec<&str> = self.linkage_evidence.split(';').collect();
+        if let Some(invalid) = evidence.iter().find(|e| !LINKAGE_EVIDENCE.contains(e)) {
+            return Err(format!("Invalid AGP linkage evidence: {}", invalid));
+        }
+        if self.linkage && evidence.contains(&"na") {
+            return Err("Linkage evidence 'na' requires linkage 'no'".to_string());
+        }
+        if !self.linkage && self.linkage_evidence != "na" {
+            return Err("Linkage 'no' requires linkage evidence 'na'".to_string());
+        }
+        if self.gap_type == "scaffold" && !self.linkage {
+            return Err("Gap type 'scaffold' requires linkage 'yes'".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// 写入AGP文件头
+pub fn write_agp_header<W: Write>(writer: &mut W) -> io::Result<()> {
+    writeln!(writer, "##agp-version\t2.1")
+}
+
+/// 根据单条scaffold的切割位置写出AGP记录：每个片段一行W，片段之间的N区间一行N或U
+/// AGP对象不能以gap开头或结尾，因此对象坐标从第一个片段开始计算，两端的N不写入；
+/// 被--min-length丢弃的片段无法写成gap（否则真实碱基会被当作N），遇到时返回错误
+pub fn write_agp<W: Write>(writer: &mut W, positions: &[SplitPosition], options: &AgpOptions) -> io::Result<()> {
+    if let Some(dropped) = positions.iter().find(|position| position.status == FragmentStatus::Dropped) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Fragment {}:{}-{} was dropped by --min-length and cannot be described in AGP", dropped.id, dropped.start, dropped.end),
+        ));
+    }
+    let offset = match positions.first() {
+        Some(first) => first.start - 1,
+        None => return Ok(()),
+    };
+    let linkage = if options.linkage { "yes" } else { "no" };
+
+    let mut part_number = 1;
+    for (i, fragment) in positions.iter().enumerate() {
+        if i > 0 {
+            let gap_start = positions[i - 1].end + 1;
+            let gap_length = fragment.start - gap_start;
+            let component_type = if options.unknown_gap_length == Some(gap_length) { "U" } else { "N" };
+            writeln!(writer, "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}", fragment.id, gap_start - offset, fragment.start - 1 - offset,
+                part_number, component_type, gap_length, options.gap_type, linkage, options.linkage_evidence)?;
+            part_number += 1;
+        }
+
+        let length = fragment.end + 1 - fragment.start;
+        writeln!(writer, "{}\t{}\t{}\t{}\tW\t{}\t1\t{}\t+", fragment.id, fragment.start - offset, fragment.end - offset,
+            part_number, fragment.new_id, length)?;
+        part_number += 1;
+    }
+    Ok(())
+}
+
+/// AGP记录第5列之后的内容：序列组分或gap
+pub enum AgpComponent {
+    Sequence {
+        id: String,       // 组分（contig）标识符
+        start: usize,     // 组分起始位置（1-based，闭区间）
+        end: usize,       // 组分终止位置（闭区间）
+        reverse: bool,    // 方向为'-'时需要反向互补
+    },
+    Gap {
+        length: usize,    // gap长度
+    },
+}
+
+/// 代表AGP文件中一行记录的结构体
+pub struct AgpRecord {
+    pub object: String,      // 对象（scaffold）标识符
+    pub object_end: usize,   // 对象终止位置（1-based，闭区间）
+    pub component: AgpComponent,
+    pub line_number: usize,  // 所在行号，用于报错
+}
+
+fn invalid_agp(line_number: usize, message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("Invalid AGP line {}: {}", line_number, message))
+}
+
+/// 从指定文件路径读取并解析AGP文件
+/// 检查每行对象区间与组分区间的长度是否一致，以及同一对象的记录是否连续且首尾相接
+pub fn read_agp(file_path: &str) -> io::Result<Vec<AgpRecord>> {
+    let reader = open_input(file_path)?;
+    let mut records: Vec<AgpRecord> = Vec::new();
+    let mut objects: HashSet<String> = HashSet::new(); // 已经出现过的对象
+
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line_number = index + 1;
+        if line.starts_with('#') || line.trim().is_empty() {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.trim_end().split('\t').collect();
+        if fields.len() < 8 {
+            return Err(invalid_agp(line_number, "expected at least 8 columns"));
+        }
+        let parse = |field: &str, name: &str| {
+            field.parse::<usize>().map_err(|_| invalid_agp(line_number, &format!("invalid {} '{}'", name, field)))
+        };
+        let object_start = parse(fields[1], "object_beg")?;
+        let object_end = parse(fields[2], "object_end")?;
+        if object_start == 0 || object_end < object_start {
+            return Err(invalid_agp(line_number, "object_beg and object_end out of order"));
+        }
+
+        let component = match fields[4] {
+            "N" | "U" => AgpComponent::Gap { length: parse(fields[5], "gap_length")? },
+            "A" | "D" | "F" | "G" | "O" | "P" | "W" => {
+                if fields.len() < 9 {
+                    return Err(invalid_agp(line_number, "expected 9 columns for a component line"));
+                }
+                let reverse = match fields[8] {
+                    "-" => true,
+                    "+" | "?" | "0" | "na" => false,
+                    other => return Err(invalid_agp(line_number, &format!("invalid orientation '{}'", other))),
+                };
+                AgpComponent::Sequence {
+                    id: fields[5].to_string(),
+                    start: parse(fields[6], "component_beg")?,
+                    end: parse(fields[7], "component_end")?,
+                    reverse,
+                }
+            }
+            other => return Err(invalid_agp(line_number, &format!("unknown component type '{}'", other))),
+        };
+
+        // 对象区间长度必须等于组分区间长度或gap长度
+        let component_length = match &component {
+            AgpComponent::Sequence { start, end, .. } => {
+                if *start == 0 || end < start {
+                    return Err(invalid_agp(line_number, "component_beg and component_end out of order"));
+                }
+                end - start + 1
+            }
+            AgpComponent::Gap { length } => *length,
+        };
+        if object_end - object_start + 1 != component_length {
+            return Err(invalid_agp(line_number, "object length does not match component length"));
+        }
+
+        // 同一对象的记录必须连续出现，从1开始首尾相接
+        let expected_start = match records.last() {
+            Some(previous) if previous.object == fields[0] => previous.object_end + 1,
+            _ => {
+                if !objects.insert(fields[0].to_string()) {
+                    return Err(invalid_agp(line_number, &format!("object {} appears again after other objects", fields[0])));
+                }
+                1
+            }
+        };
+        if object_start != expected_start {
+            return Err(invalid_agp(line_number, &format!("object_beg should be {}", expected_start)));
+        }
+
+        records.push(AgpRecord {
+            object: fields[0].to_string(),
+            object_end,
+            component,
+            line_number,
+        });
+    }
+
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fasta::FastaSequence;
+    use crate::split::{split_sequence, BreakRule, SplitOptions};
+    use crate::test_utils::write_temp;
+
+    fn gap_options(unknown_gap_length: Option<usize>) -> AgpOptions {
+        AgpOptions {
+            gap_type: "scaffold".to_string(),
+            linkage: true,
+            linkage_evidence: "paired-ends".to_string(),
+            unknown_gap_length,
+        }
+    }
+
+    fn agp_lines(bases: &str, min_length: usize, options: &AgpOptions) -> Vec<String> {
+        let seq = FastaSequence { id: "scaf1".to_string(), description: String::new(), sequence: bases.to_string(), quality: None };
+        let split_options = SplitOptions { break_rule: BreakRule::N, min_gap: 1, min_length, keep_short: false };
+        let (_, positions) = split_sequence(&seq, &split_options);
+        let mut output = Vec::new();
+        write_agp(&mut output, &positions, options).unwrap();
+        String::from_utf8(output).unwrap().lines().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn test_validate_options() {
+        assert!(gap_options(None).validate().is_ok());
+        let mut options = gap_options(None);
+        options.linkage = false;
+        assert!(options.validate().is_err());
+        options.gap_type = "contig".to_string();
+        options.linkage_evidence = "na".to_string();
+        assert!(options.validate().is_ok());
+        options.gap_type = "unknown".to_string();
+        assert!(options.validate().is_err());
+    }
+
+    #[test]
+    fn test_write_agp_skips_terminal_gaps() {
+        let lines = agp_lines("NNACGTNNNNNGGNNNNNNNNNNTTTN", 0, &gap_options(Some(10)));
+        assert_eq!(lines, vec![
+            "scaf1\t1\t4\t1\tW\tscaf1_1\t1\t4\t+",
+            "scaf1\t5\t9\t2\tN\t5\tscaffold\tyes\tpaired-ends",
+            "scaf1\t10\t11\t3\tW\tscaf1_2\t1\t2\t+",
+            "scaf1\t12\t21\t4\tU\t10\tscaffold\tyes\tpaired-ends",
+            "scaf1\t22\t24\t5\tW\tscaf1_3\t1\t3\t+",
+        ]);
+    }
+
+    #[test]
+    fn test_write_agp_rejects_dropped_fragments() {
+        let seq = FastaSequence { id: "scaf1".to_string(), description: String::new(), sequence: "ACGTNNGGNNTTTT".to_string(), quality: None };
+        let split_options = SplitOptions { break_rule: BreakRule::N, min_gap: 1, min_length: 3, keep_short: false };
+        let (_, positions) = split_sequence(&seq, &split_options);
+        let error = write_agp(&mut Vec::new(), &positions, &gap_options(None)).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        assert!(error.to_string().contains("scaf1:7-8"));
+    }
+
+    #[test]
+    fn test_write_read_round_trip() {
+        let mut content = Vec::new();
+        write_agp_header(&mut content).unwrap();
+        for line in agp_lines("ACGTNNNNNGGNNNNNNNNNNTTT", 0, &gap_options(Some(10))) {
+            content.extend_from_slice(line.as_bytes());
+            content.push(b'\n');
+        }
+        let path = write_temp("round_trip.agp", &content);
+
+        let records = read_agp(&path).unwrap();
+        assert_eq!(records.len(), 5);
+        assert!(records.iter().all(|record| record.object == "scaf1"));
+        assert_eq!(records.last().unwrap().object_end, 24);
+        match &records[2].component {
+            AgpComponent::Sequence { id, start, end, reverse } => {
+                assert_eq!((id.as_str(), *start, *end, *reverse), ("scaf1_2", 1, 2, false));
+            }
+            AgpComponent::Gap { .. } => panic!("expected a component line"),
+        }
+        assert!(matches!(records[3].component, AgpComponent::Gap { length: 10 }));
+    }
+
+    #[test]
+    fn test_read_agp_rejects_inconsistent_lines() {
+        // 对象区间与组分区间长度不一致
+        let path = write_temp("bad_length.agp", b"scaf1\t1\t4\t1\tW\tctg1\t1\t5\t+\n");
+        assert!(read_agp(&path).is_err());
+        // 同一对象的记录不连续
+        let path = write_temp("bad_order.agp", b"scaf1\t1\t4\t1\tW\tctg1\t1\t4\t+\nscaf1\t6\t9\t2\tW\tctg2\t1\t4\t+\n");
+        assert!(read_agp(&path).is_err());
+        // 未知的组分类型
+        let path = write_temp("bad_type.agp", b"scaf1\t1\t4\t1\tX\tctg1\t1\t4\t+\n");
+        assert!(read_agp(&path).is_err());
+    }
+
+    #[test]
+    fn test_read_agp_rejects_split_objects() {
+        // 对象在其他对象之后再次出现，即使坐标重新从1开始
+        let path = write_temp("split_object.agp", b"scaf1\t1\t4\t1\tW\tctg1\t1\t4\t+\nscaf2\t1\t4\t1\tW\tctg2\t1\t4\t+\nscaf1\t1\t4\t1\tW\tctg3\t1\t4\t+\n");
+        let error = read_agp(&path).err().unwrap();
+        assert!(error.to_string().contains("line 3"));
+    }
+}
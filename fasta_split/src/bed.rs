@@ -1,5 +1,5 @@
-use std::fs::File;
-use std::io::{self, BufRead, BufReader};
+use crate::compress::open_input;
+use std::io::{self, BufRead};
 
 /// 代表BED记录的结构体
 pub struct BedRecord {
@@ -7,26 +7,104 @@ pub struct BedRecord {
     pub start: i64,        // 起始位置（左闭）
     pub end: i64,          // 终止位置（右开）
     pub name: Option<String>, // 可选的名称/ID
+    pub description: Option<String>, // 输出序列头中的描述信息（BED文件中没有，由GFF等来源提供）
+    pub reverse: bool,     // 第6列链方向为'-'时为true
+    pub thick_start: i64,  // 编码区起始位置（第7列，缺省为start）
+    pub thick_end: i64,    // 编码区终止位置（第8列，缺省为end）
+    pub blocks: Vec<(i64, i64)>, // 外显子块的绝对坐标[start, end)，非BED12时为整个区间
+}
+
+fn invalid_bed(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+/// 解析BED12中以逗号分隔的整数列表（允许末尾多一个逗号）
+fn parse_list(field: &str, message: &str) -> Result<Vec<i64>, io::Error> {
+    field.trim_end_matches(',')
+        .split(',')
+        .map(|value| value.parse().map_err(|_| invalid_bed(message)))
+        .collect()
+}
+
+/// 判断是否为track或browser行：关键字后面必须是空白或行尾，以免跳过以这些词开头的染色体
+fn is_header_line(line: &str, keyword: &str) -> bool {
+    line.strip_prefix(keyword).is_some_and(|rest| rest.is_empty() || rest.starts_with(char::is_whitespace))
+}
+
+/// 解析BED12的第10-12列，返回外显子块的绝对坐标
+fn parse_blocks(fields: &[&str], start: i64, end: i64) -> Result<Vec<(i64, i64)>, io::Error> {
+    let count: usize = fields[9].parse().map_err(|_| invalid_bed("Invalid blockCount"))?;
+    let sizes = parse_list(fields[10], "Invalid blockSizes")?;
+    let starts = parse_list(fields[11], "Invalid blockStarts")?;
+    if count == 0 || sizes.len() != count || starts.len() != count {
+        return Err(invalid_bed("blockCount does not match blockSizes/blockStarts"));
+    }
+
+    let mut blocks = Vec::with_capacity(count);
+    let mut previous_end = start;
+    for (&size, &offset) in sizes.iter().zip(starts.iter()) {
+        let block = (start + offset, start + offset + size);
+        // 外显子块必须按顺序排列、互不重叠，且位于记录区间内
+        if size <= 0 || block.0 < previous_end || block.1 > end {
+            return Err(invalid_bed("Invalid BED12 blocks"));
+        }
+        previous_end = block.1;
+        blocks.push(block);
+    }
+    if blocks[0].0 != start || previous_end != end {
+        return Err(invalid_bed("BED12 blocks do not span chromStart to chromEnd"));
+    }
+    Ok(blocks)
 }
 
 /// 从指定文件路径读取并解析BED文件
+/// 支持BED3到BED12，跳过track、browser和以'#'开头的行
 /// 返回一个包含所有BED记录的向量
 pub fn read_bed(file_path: &str) -> Result<Vec<BedRecord>, io::Error> {
-    let file = File::open(file_path)?;
-    let reader = BufReader::new(file);
+    let reader = open_input(file_path)?;
 
     let mut records = Vec::new();
     for line in reader.lines() {
         let line = line?;
+        if line.is_empty() || line.starts_with('#') || is_header_line(&line, "track") || is_header_line(&line, "browser") {
+            continue;
+        }
         let fields: Vec<&str> = line.split('\t').collect();
-        if fields.len() < 3 || fields.len() > 4 {
-            return Err(io::Error::new(io::ErrorKind::InvalidData, "Invalid BED format"));
+        if fields.len() < 3 || fields.len() > 12 {
+            return Err(invalid_bed("Invalid BED format"));
         }
+        let start: i64 = fields[1].parse().map_err(|_| invalid_bed("Invalid start position"))?;
+        let end: i64 = fields[2].parse().map_err(|_| invalid_bed("Invalid end position"))?;
+        // BED6的第5列为score（不使用），第6列为链方向
+        let reverse = match fields.get(5) {
+            None | Some(&".") | Some(&"+") => false,
+            Some(&"-") => true,
+            Some(_) => return Err(invalid_bed("Invalid strand")),
+        };
+        // 第7、8列为编码区范围，第9列为显示颜色（不使用）
+        let thick_start = match fields.get(6) {
+            Some(field) => field.parse().map_err(|_| invalid_bed("Invalid thickStart"))?,
+            None => start,
+        };
+        let thick_end = match fields.get(7) {
+            Some(field) => field.parse().map_err(|_| invalid_bed("Invalid thickEnd"))?,
+            None => end,
+        };
+        let blocks = match fields.len() {
+            12 => parse_blocks(&fields, start, end)?,
+            10 | 11 => return Err(invalid_bed("Incomplete BED12 block columns")),
+            _ => vec![(start, end)],
+        };
         let record = BedRecord {
             chrom: fields[0].to_string(),
-            start: fields[1].parse().map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "Invalid start position"))?,
-            end: fields[2].parse().map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "Invalid end position"))?,
-            name: if fields.len() == 4 { Some(fields[3].to_string()) } else { None },
+            start,
+            end,
+            name: if fields.len() >= 4 { Some(fields[3].to_string()) } else { None },
+            description: None,
+            reverse,
+            thick_start,
+            thick_end,
+            blocks,
         };
         records.push(record);
     }
@@ -34,4 +112,57 @@ pub fn read_bed(file_path: &str) -> Result<Vec<BedRecord>, io::Error> {
     Ok(records)
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::write_temp;
+
+    #[test]
+    fn test_read_bed6_strand() {
+        let path = write_temp("strand.bed", b"track name=test\nchr1\t0\t10\nchr1\t5\t20\tgene1\t0\t-\n#comment\nchr2\t1\t4\tgene2\t0\t+\n");
+        let records = read_bed(&path).unwrap();
+        assert_eq!(records.len(), 3);
+        assert!(records[0].name.is_none());
+        assert!(!records[0].reverse);
+        assert_eq!(records[1].name.as_deref(), Some("gene1"));
+        assert!(records[1].reverse);
+        assert_eq!((records[1].thick_start, records[1].thick_end), (5, 20));
+        assert_eq!(records[1].blocks, vec![(5, 20)]);
+        assert!(!records[2].reverse);
+
+        let path = write_temp("bad_strand.bed", b"chr1\t0\t10\tgene1\t0\tx\n");
+        assert!(read_bed(&path).is_err());
+    }
 
+    #[test]
+    fn test_read_bed12_blocks() {
+        let path = write_temp("blocks.bed", b"chr1\t10\t40\ttx1\t0\t+\t15\t35\t0\t3\t5,4,6,\t0,12,24\n");
+        let records = read_bed(&path).unwrap();
+        assert_eq!(records[0].blocks, vec![(10, 15), (22, 26), (34, 40)]);
+        assert_eq!((records[0].thick_start, records[0].thick_end), (15, 35));
+    }
+
+    #[test]
+    fn test_read_bed12_rejects_invalid_blocks() {
+        // 块数与列表长度不一致
+        let path = write_temp("count.bed", b"chr1\t10\t40\ttx1\t0\t+\t10\t40\t0\t2\t5,4,6\t0,12,24\n");
+        assert!(read_bed(&path).is_err());
+        // 外显子块互相重叠
+        let path = write_temp("overlap.bed", b"chr1\t10\t40\ttx1\t0\t+\t10\t40\t0\t2\t15,20\t0,10\n");
+        assert!(read_bed(&path).is_err());
+        // 外显子块没有覆盖到chromEnd
+        let path = write_temp("span.bed", b"chr1\t10\t40\ttx1\t0\t+\t10\t40\t0\t2\t5,5\t0,20\n");
+        assert!(read_bed(&path).is_err());
+        // 缺少BED12的块列
+        let path = write_temp("partial.bed", b"chr1\t10\t40\ttx1\t0\t+\t10\t40\t0\t2\n");
+        assert!(read_bed(&path).is_err());
+    }
+
+    #[test]
+    fn test_track_and_browser_lines() {
+        let path = write_temp("headers.bed", b"browser position chr1:1-100\ntrack\ntrackA\t0\t4\nbrowser_2\t1\t3\n");
+        let records = read_bed(&path).unwrap();
+        let chroms: Vec<&str> = records.iter().map(|r| r.chrom.as_str()).collect();
+        assert_eq!(chroms, vec!["trackA", "browser_2"]);
+    }
+}
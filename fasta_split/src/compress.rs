@@ -0,0 +1,286 @@
+use flate2::write::{DeflateEncoder, GzEncoder};
+use flate2::Crc;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+/// 压缩输入的识别与读取由三个工具共用的seq_utils提供
+pub use seq_utils::{open_input, GZIP_MAGIC};
+
+/// 输出文件的压缩方式
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None, // 不压缩
+    Gzip, // 普通gzip
+    Bgzf, // 分块gzip，兼容bgzip/samtools
+}
+
+impl Compression {
+    /// 根据命令行参数解析压缩方式
+    pub fn from_name(name: &str) -> Option<Compression> {
+        match name {
+            "none" => Some(Compression::None),
+            "gzip" => Some(Compression::Gzip),
+            "bgzf" => Some(Compression::Bgzf),
+            _ => None,
+        }
+    }
+
+    /// 根据输出文件的扩展名推测压缩方式
+    pub fn from_path(file_path: &str) -> Compression {
+        if file_path.ends_with(".bgz") || file_path.ends_with(".bgzf") {
+            Compression::Bgzf
+        } else if file_path.ends_with(".gz") {
+            Compression::Gzip
+        } else {
+            Compression::None
+        }
+    }
+}
+
+/// 单个BGZF块最多容纳的未压缩数据量，与bgzip保持一致
+const BGZF_BLOCK_SIZE: usize = 0xff00;
+
+/// BGZF文件末尾的空块
+const BGZF_EOF: [u8; 28] = [
+    0x1f, 0x8b, 0x08, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0x06, 0x00, 0x42, 0x43,
+    0x02, 0x00, 0x1b, 0x00, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
+
+/// BGZF格式的写入器
+/// 数据按块压缩，每块都是一个带BC扩展字段的gzip成员
+pub struct BgzfWriter<W: Write> {
+    inner: W,
+    buffer: Vec<u8>,              // 当前块中尚未压缩的数据
+    compressed_offset: u64,       // 已写出的压缩数据长度
+    uncompressed_offset: u64,     // 已写出的未压缩数据长度
+    block_offsets: Vec<(u64, u64)>, // 除第一块外每个块的（压缩偏移，未压缩偏移）
+}
+
+impl<W: Write> BgzfWriter<W> {
+    pub fn new(inner: W) -> Self {
+        BgzfWriter {
+            inner,
+            buffer: Vec::with_capacity(BGZF_BLOCK_SIZE),
+            compressed_offset: 0,
+            uncompressed_offset: 0,
+            block_offsets: Vec::new(),
+        }
+    }
+
+    /// 压缩并写出缓冲区中的数据块
+    fn write_block(&mut self) -> io::Result<()> {
+        if self.compressed_offset > 0 {
+            self.block_offsets.push((self.compressed_offset, self.uncompressed_offset));
+        }
+
+        let mut encoder = DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&self.buffer)?;
+        let compressed = encoder.finish()?;
+        let mut crc = Crc::new();
+        crc.update(&self.buffer);
+
+        // 块头18字节 + 压缩数据 + CRC32和原始长度8字节
+        let block_size = 18 + compressed.len() + 8;
+        let bsize = u16::try_from(block_size - 1)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "BGZF block too large"))?;
+        self.inner.write_all(&[0x1f, 0x8b, 0x08, 0x04, 0, 0, 0, 0, 0, 0xff, 0x06, 0x00, 0x42, 0x43, 0x02, 0x00])?;
+        self.inner.write_all(&bsize.to_le_bytes())?;
+        self.inner.write_all(&compressed)?;
+        self.inner.write_all(&crc.sum().to_le_bytes())?;
+        self.inner.write_all(&(self.buffer.len() as u32).to_le_bytes())?;
+
+        self.compressed_offset += block_size as u64;
+        self.uncompressed_offset += self.buffer.len() as u64;
+        self.buffer.clear();
+        Ok(())
+    }
+
+    /// 写出剩余数据和EOF块，返回.gzi索引所需的块偏移
+    pub fn finish(mut self) -> io::Result<Vec<(u64, u64)>> {
+        if !self.buffer.is_empty() {
+            self.write_block()?;
+        }
+        self.inner.write_all(&BGZF_EOF)?;
+        self.inner.flush()?;
+        Ok(self.block_offsets)
+    }
+}
+
+impl<W: Write> Write for BgzfWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let count = buf.len().min(BGZF_BLOCK_SIZE - self.buffer.len());
+        self.buffer.extend_from_slice(&buf[..count]);
+        if self.buffer.len() == BGZF_BLOCK_SIZE {
+            self.write_block()?;
+        }
+        Ok(count)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// 将BGZF块偏移写成bgzip兼容的.gzi索引文件
+/// 格式：条目数（u64），随后每个条目为压缩偏移和未压缩偏移（u64，小端）
+pub fn write_gzi(block_offsets: &[(u64, u64)], file_path: &str) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(file_path)?);
+    writer.write_all(&(block_offsets.len() as u64).to_le_bytes())?;
+    for &(compressed, uncompressed) in block_offsets {
+        writer.write_all(&compressed.to_le_bytes())?;
+        writer.write_all(&uncompressed.to_le_bytes())?;
+    }
+    writer.flush()
+}
+
+/// 检查.gzi索引设置：只有BGZF输出才能生成.gzi索引
+pub fn check_gzi(file_path: &str, compression: Compression, write_index: bool) -> io::Result<()> {
+    if write_index && compression != Compression::Bgzf {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("--gzi requires BGZF output, but {} is not BGZF compressed; use --compress bgzf or a .bgz extension", file_path),
+        ));
+    }
+    Ok(())
+}
+
+/// 统一的输出写入器，根据压缩方式选择底层实现
+pub enum OutputWriter {
+    Plain(BufWriter<File>),
+    Gzip(GzEncoder<BufWriter<File>>),
+    Bgzf(BgzfWriter<BufWriter<File>>, Option<String>), // 第二项为.gzi索引路径
+}
+
+impl OutputWriter {
+    /// 创建输出文件；BGZF输出时可同时生成`<file_path>.gzi`索引，其他压缩方式要求索引时返回错误
+    pub fn create(file_path: &str, compression: Compression, write_index: bool) -> io::Result<OutputWriter> {
+        check_gzi(file_path, compression, write_index)?;
+        let file = BufWriter::new(File::create(file_path)?);
+        Ok(match compression {
+            Compression::None => OutputWriter::Plain(file),
+            Compression::Gzip => OutputWriter::Gzip(GzEncoder::new(file, flate2::Compression::default())),
+            Compression::Bgzf => {
+                let index_path = if write_index { Some(format!("{}.gzi", file_path)) } else { None };
+                OutputWriter::Bgzf(BgzfWriter::new(file), index_path)
+            }
+        })
+    }
+
+    /// 完成写入：写出压缩流的结尾，并在需要时写出.gzi索引
+    pub fn finish(self) -> io::Result<()> {
+        match self {
+            OutputWriter::Plain(mut writer) => writer.flush(),
+            OutputWriter::Gzip(encoder) => encoder.finish()?.flush(),
+            OutputWriter::Bgzf(writer, index_path) => {
+                let block_offsets = writer.finish()?;
+                match index_path {
+                    Some(path) => write_gzi(&block_offsets, &path),
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+}
+
+impl Write for OutputWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            OutputWriter::Plain(writer) => writer.write(buf),
+            OutputWriter::Gzip(writer) => writer.write(buf),
+            OutputWriter::Bgzf(writer, _) => writer.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            OutputWriter::Plain(writer) => writer.flush(),
+            OutputWriter::Gzip(writer) => writer.flush(),
+            OutputWriter::Bgzf(writer, _) => writer.flush(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::temp_path;
+    use flate2::read::MultiGzDecoder;
+    use std::io::Read;
+
+    fn test_data(len: usize) -> Vec<u8> {
+        (0..len).map(|i| b"ACGTN\n"[i % 6]).collect()
+    }
+
+    #[test]
+    fn test_bgzf_round_trip_and_block_offsets() {
+        let data = test_data(3 * BGZF_BLOCK_SIZE + 100);
+        let mut compressed = Vec::new();
+        let mut writer = BgzfWriter::new(&mut compressed);
+        writer.write_all(&data).unwrap();
+        let offsets = writer.finish().unwrap();
+
+        // 多成员gzip解压后与原始数据一致，文件以EOF块结尾
+        let mut decompressed = Vec::new();
+        MultiGzDecoder::new(&compressed[..]).read_to_end(&mut decompressed).unwrap();
+        assert_eq!(decompressed, data);
+        assert!(compressed.ends_with(&BGZF_EOF));
+
+        // 第一块之外的每一块都有索引条目，未压缩偏移是块大小的整数倍
+        assert_eq!(offsets.len(), 3);
+        for (i, &(compressed_offset, uncompressed_offset)) in offsets.iter().enumerate() {
+            assert_eq!(uncompressed_offset, ((i + 1) * BGZF_BLOCK_SIZE) as u64);
+            let block = &compressed[compressed_offset as usize..];
+            assert_eq!(&block[..4], &[0x1f, 0x8b, 0x08, 0x04]);
+            assert_eq!(&block[12..14], b"BC");
+        }
+
+        // 每块头部的BSIZE指向下一块的起点
+        let mut position = 0;
+        let mut block_starts = Vec::new();
+        while position < compressed.len() {
+            block_starts.push(position as u64);
+            let bsize = u16::from_le_bytes([compressed[position + 16], compressed[position + 17]]) as usize;
+            position += bsize + 1;
+        }
+        assert_eq!(position, compressed.len());
+        let expected: Vec<u64> = offsets.iter().map(|&(offset, _)| offset).collect();
+        assert_eq!(&block_starts[1..block_starts.len() - 1], &expected[..]);
+    }
+
+    #[test]
+    fn test_output_writer_writes_gzi() {
+        let path = temp_path("out.fa.bgz");
+        let data = test_data(BGZF_BLOCK_SIZE + 10);
+        let mut writer = OutputWriter::create(&path, Compression::Bgzf, true).unwrap();
+        writer.write_all(&data).unwrap();
+        writer.finish().unwrap();
+
+        let gzi = std::fs::read(format!("{}.gzi", path)).unwrap();
+        assert_eq!(gzi.len(), 8 + 16);
+        assert_eq!(u64::from_le_bytes(gzi[0..8].try_into().unwrap()), 1);
+        assert_eq!(u64::from_le_bytes(gzi[16..24].try_into().unwrap()), BGZF_BLOCK_SIZE as u64);
+
+        let mut decompressed = String::new();
+        open_input(&path).unwrap().read_to_string(&mut decompressed).unwrap();
+        assert_eq!(decompressed.as_bytes(), &data[..]);
+    }
+
+    #[test]
+    fn test_gzi_requires_bgzf() {
+        for compression in [Compression::None, Compression::Gzip] {
+            let path = temp_path("no_gzi.fa.gz");
+            let error = OutputWriter::create(&path, compression, true).err().unwrap();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+            assert!(!std::path::Path::new(&path).exists());
+        }
+        assert!(check_gzi("a.fa", Compression::None, false).is_ok());
+        assert!(check_gzi("a.fa.bgz", Compression::Bgzf, true).is_ok());
+    }
+
+    #[test]
+    fn test_compression_from_path() {
+        assert!(Compression::from_path("a.fa.bgz") == Compression::Bgzf);
+        assert!(Compression::from_path("a.fa.gz") == Compression::Gzip);
+        assert!(Compression::from_path("a.fa") == Compression::None);
+    }
+}
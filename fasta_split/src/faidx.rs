@@ -0,0 +1,352 @@
+use crate::compress::{open_input, GZIP_MAGIC};
+use crate::split_bed::RegionSource;
+use flate2::read::MultiGzDecoder;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// 代表.fai索引中一条记录的结构体（与samtools faidx的五列格式一致）
+pub struct FaiRecord {
+    pub name: String,     // 序列名
+    pub length: u64,      // 序列长度
+    pub offset: u64,      // 序列第一个碱基在文件中的偏移（未压缩坐标）
+    pub line_bases: u64,  // 每行碱基数
+    pub line_width: u64,  // 每行字节数（含换行符）
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// 扫描FASTA文件并生成.fai索引记录
+/// 压缩文件按解压后的坐标计算偏移，与samtools对bgzip文件的处理一致
+pub fn build_index(file_path: &str) -> io::Result<Vec<FaiRecord>> {
+    let mut reader = open_input(file_path)?;
+    let mut records: Vec<FaiRecord> = Vec::new();
+    let mut line = Vec::new();
+    let mut position: u64 = 0;      // 当前行在文件中的偏移
+    let mut last_line_seen = false; // 当前序列是否已出现只能作为最后一行的行
+
+    loop {
+        line.clear();
+        let width = reader.read_until(b'\n', &mut line)? as u64;
+        if width == 0 {
+            break;
+        }
+        let line_start = position;
+        position += width;
+
+        if line.first() == Some(&b'@') && records.is_empty() {
+            return Err(invalid_data("FASTQ input is not supported by faidx".to_string()));
+        }
+        if line.first() == Some(&b'>') {
+            // 解析序列头，序列名取第一个空白字符之前的部分
+            let header = String::from_utf8_lossy(&line[1..]);
+            let name = header.split_whitespace().next().unwrap_or("").to_string();
+            records.push(FaiRecord { name, length: 0, offset: position, line_bases: 0, line_width: 0 });
+            last_line_seen = false;
+            continue;
+        }
+
+        let record = match records.last_mut() {
+            Some(record) => record,
+            None => continue, // 跳过第一条序列之前的内容
+        };
+        let bases = line.iter().filter(|&&c| c != b'\n' && c != b'\r').count() as u64;
+        if bases == 0 {
+            last_line_seen = true;
+            continue;
+        }
+        if record.line_bases == 0 {
+            record.line_bases = bases;
+            record.line_width = width;
+        } else if last_line_seen || bases > record.line_bases {
+            return Err(invalid_data(format!("Different line length in sequence '{}' at offset {}", record.name, line_start)));
+        }
+        // 较短的行和换行符不同的行（如文件末尾没有换行符）只能是序列的最后一行
+        if bases < record.line_bases || width != record.line_width {
+            last_line_seen = true;
+        }
+        record.length += bases;
+    }
+
+    Ok(records)
+}
+
+/// 将索引记录写入.fai文件
+pub fn write_fai(records: &[FaiRecord], file_path: &str) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(file_path)?);
+    for record in records {
+        writeln!(writer, "{}\t{}\t{}\t{}\t{}", record.name, record.length, record.offset, record.line_bases, record.line_width)?;
+    }
+    writer.flush()
+}
+
+/// 读取已有的.fai文件
+pub fn read_fai(file_path: &str) -> io::Result<Vec<FaiRecord>> {
+    let reader = BufReader::new(File::open(file_path)?);
+    let mut records = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() < 5 {
+            return Err(invalid_data(format!("Invalid FAI line: {}", line)));
+        }
+        let parse = |field: &str| field.parse::<u64>().map_err(|_| invalid_data(format!("Invalid FAI line: {}", line)));
+        let record = FaiRecord {
+            name: fields[0].to_string(),
+            length: parse(fields[1])?,
+            offset: parse(fields[2])?,
+            line_bases: parse(fields[3])?,
+            line_width: parse(fields[4])?,
+        };
+        // 非空序列每行至少一个碱基，否则无法换算偏移
+        if record.length > 0 && record.line_bases == 0 {
+            return Err(invalid_data(format!("Invalid FAI line: {}", line)));
+        }
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/// 判断.fai文件是否为FASTQ索引（六列）
+fn is_fastq_index(file_path: &str) -> io::Result<bool> {
+    let mut reader = BufReader::new(File::open(file_path)?);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    Ok(line.trim_end().split('\t').count() == 6)
+}
+
+/// 读取bgzip生成的.gzi索引，返回（压缩偏移，未压缩偏移）列表，首项固定为(0, 0)
+fn read_gzi(file_path: &str) -> io::Result<Vec<(u64, u64)>> {
+    let mut reader = BufReader::new(File::open(file_path)?);
+    let mut buffer = [0u8; 8];
+    reader.read_exact(&mut buffer)?;
+    let count = u64::from_le_bytes(buffer);
+    let mut blocks = vec![(0, 0)];
+    for _ in 0..count {
+        reader.read_exact(&mut buffer)?;
+        let compressed = u64::from_le_bytes(buffer);
+        reader.read_exact(&mut buffer)?;
+        let uncompressed = u64::from_le_bytes(buffer);
+        blocks.push((compressed, uncompressed));
+    }
+    Ok(blocks)
+}
+
+/// 带.fai索引的FASTA文件，支持按区间随机读取
+/// 纯文本文件直接定位；BGZF压缩文件借助.gzi索引定位到所在的压缩块
+pub struct IndexedFasta {
+    file: File,
+    blocks: Option<Vec<(u64, u64)>>, // BGZF文件的块偏移，纯文本文件为None
+    index: HashMap<String, FaiRecord>,
+    duplicated: HashSet<String>,     // 索引中出现多次的序列名
+}
+
+impl IndexedFasta {
+    /// 若`<fasta>.fai`存在（压缩文件还需要`<fasta>.gzi`），打开带索引的FASTA文件
+    /// 没有可用索引时返回None，调用方应退回到顺序读取
+    pub fn open(file_path: &str) -> io::Result<Option<IndexedFasta>> {
+        let fai_path = format!("{}.fai", file_path);
+        if !Path::new(&fai_path).exists() {
+            return Ok(None);
+        }
+
+        let mut file = File::open(file_path)?;
+        let mut magic = [0u8; 2];
+        let is_gzip = file.read(&mut magic)? == 2 && magic == GZIP_MAGIC;
+        let blocks = if is_gzip {
+            let gzi_path = format!("{}.gzi", file_path);
+            if !Path::new(&gzi_path).exists() {
+                return Ok(None);
+            }
+            Some(read_gzi(&gzi_path)?)
+        } else {
+            None
+        };
+
+        // samtools fqidx生成的FASTQ索引多一列质量偏移，这里只支持FASTA索引
+        if is_fastq_index(&fai_path)? {
+            return Ok(None);
+        }
+
+        let mut index = HashMap::new();
+        let mut duplicated = HashSet::new();
+        for record in read_fai(&fai_path)? {
+            if index.contains_key(&record.name) {
+                duplicated.insert(record.name.clone());
+            } else {
+                index.insert(record.name.clone(), record);
+            }
+        }
+        for name in &duplicated {
+            index.remove(name);
+        }
+        Ok(Some(IndexedFasta { file, blocks, index, duplicated }))
+    }
+
+    /// 从未压缩坐标offset处读取len个字节
+    fn read_at(&mut self, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+        let mut buffer = vec![0u8; len];
+        match &self.blocks {
+            None => {
+                self.file.seek(SeekFrom::Start(offset))?;
+                self.file.read_exact(&mut buffer)?;
+            }
+            Some(blocks) => {
+                // 找到包含offset的压缩块，从块起点解压并跳过多余的字节
+                let block = blocks.partition_point(|&(_, uncompressed)| uncompressed <= offset) - 1;
+                let (compressed, uncompressed) = blocks[block];
+                self.file.seek(SeekFrom::Start(compressed))?;
+                let mut decoder = MultiGzDecoder::new(BufReader::new(&mut self.file));
+                io::copy(&mut (&mut decoder).take(offset - uncompressed), &mut io::sink())?;
+                decoder.read_exact(&mut buffer)?;
+            }
+        }
+        Ok(buffer)
+    }
+}
+
+impl RegionSource for IndexedFasta {
+    fn sequence_length(&self, chrom: &str) -> Option<usize> {
+        self.index.get(chrom).map(|record| record.length as usize)
+    }
+
+    fn is_duplicated(&self, chrom: &str) -> bool {
+        self.duplicated.contains(chrom)
+    }
+
+    fn fetch(&mut self, chrom: &str, start: usize, end: usize) -> io::Result<(String, Option<String>)> {
+        let record = self.index.get(chrom)
+            .ok_or_else(|| invalid_data(format!("Sequence {} not found in FAI index", chrom)))?;
+        if start >= end {
+            return Ok((String::new(), None));
+        }
+
+        // 根据行宽把碱基坐标换算成文件偏移
+        let locate = |pos: u64| record.offset + pos / record.line_bases * record.line_width + pos % record.line_bases;
+        let first = locate(start as u64);
+        let last = locate(end as u64 - 1);
+        let bytes = self.read_at(first, (last - first + 1) as usize)?;
+
+        let sequence: Vec<u8> = bytes.into_iter().filter(|&c| c != b'\n' && c != b'\r').collect();
+        let sequence = String::from_utf8(sequence).map_err(|_| invalid_data(format!("Invalid sequence data in {}", chrom)))?;
+        Ok((sequence, None))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compress::{Compression, OutputWriter};
+    use crate::test_utils::{temp_path, write_temp};
+
+    const FASTA: &[u8] = b">chr1 first\nACGTA\nCGTAC\nGT\n>chr2\nTTTT\nGG\n";
+
+    #[test]
+    fn test_build_index_offsets() {
+        let path = write_temp("index.fa", FASTA);
+        let records = build_index(&path).unwrap();
+        assert_eq!(records.len(), 2);
+        let fields: Vec<_> = records.iter()
+            .map(|r| (r.name.as_str(), r.length, r.offset, r.line_bases, r.line_width))
+            .collect();
+        assert_eq!(fields, vec![("chr1", 12, 12, 5, 6), ("chr2", 6, 33, 4, 5)]);
+
+        // 写出再读回，五列保持不变
+        let fai_path = format!("{}.fai", path);
+        write_fai(&records, &fai_path).unwrap();
+        let reread = read_fai(&fai_path).unwrap();
+        assert_eq!(reread.len(), 2);
+        assert_eq!((reread[1].name.as_str(), reread[1].offset), ("chr2", 33));
+        assert!(!is_fastq_index(&fai_path).unwrap());
+    }
+
+    #[test]
+    fn test_build_index_last_line_without_newline() {
+        let path = write_temp("no_newline.fa", b">a\nACGT\nACGT");
+        let records = build_index(&path).unwrap();
+        assert_eq!((records[0].length, records[0].offset, records[0].line_bases, records[0].line_width), (8, 3, 4, 5));
+
+        // 换行符不同的最后一行后面紧跟序列头
+        let path = write_temp("last_line.fa", b">a\r\nACGT\r\nACGT\n>b\nAC");
+        write_fai(&build_index(&path).unwrap(), &format!("{}.fai", path)).unwrap();
+        let mut fasta = IndexedFasta::open(&path).unwrap().unwrap();
+        assert_eq!(fasta.fetch("a", 2, 8).unwrap().0, "GTACGT");
+        assert_eq!(fasta.fetch("b", 0, 2).unwrap().0, "AC");
+
+        // 换行符不同的行之后还有序列行时仍然报错
+        let path = write_temp("mixed_newline.fa", b">a\nACGT\nACGT\r\nACGT\n");
+        assert!(build_index(&path).is_err());
+    }
+
+    #[test]
+    fn test_read_fai_rejects_zero_line_bases() {
+        let path = write_temp("zero_bases.fai", b"chr1\t10\t6\t0\t0\n");
+        assert!(read_fai(&path).is_err());
+        let path = write_temp("empty_sequence.fai", b"chr1\t0\t6\t0\t0\n");
+        assert_eq!(read_fai(&path).unwrap()[0].length, 0);
+    }
+
+    #[test]
+    fn test_build_index_rejects_uneven_lines() {
+        let path = write_temp("uneven.fa", b">chr1\nACG\nACGTA\n");
+        assert!(build_index(&path).is_err());
+        let path = write_temp("reads.fq", b"@r1\nACGT\n+\nIIII\n");
+        assert!(build_index(&path).is_err());
+    }
+
+    #[test]
+    fn test_fetch_plain_region() {
+        let path = write_temp("fetch.fa", FASTA);
+        assert!(IndexedFasta::open(&path).unwrap().is_none());
+        write_fai(&build_index(&path).unwrap(), &format!("{}.fai", path)).unwrap();
+
+        let mut fasta = IndexedFasta::open(&path).unwrap().unwrap();
+        assert_eq!(fasta.sequence_length("chr1"), Some(12));
+        assert_eq!(fasta.fetch("chr1", 3, 11).unwrap().0, "TACGTACG");
+        assert_eq!(fasta.fetch("chr1", 0, 12).unwrap().0, "ACGTACGTACGT");
+        assert_eq!(fasta.fetch("chr2", 2, 6).unwrap().0, "TTGG");
+        assert_eq!(fasta.fetch("chr2", 3, 3).unwrap().0, "");
+        assert!(fasta.fetch("chr3", 0, 1).is_err());
+    }
+
+    #[test]
+    fn test_fetch_bgzf_region_across_blocks() {
+        // 单条长序列跨越多个BGZF块
+        let bases: String = (0..200_000).map(|i| ['A', 'C', 'G', 'T'][(i / 7) % 4]).collect();
+        let mut content = b">long\n".to_vec();
+        for line in bases.as_bytes().chunks(60) {
+            content.extend_from_slice(line);
+            content.push(b'\n');
+        }
+        let path = temp_path("fetch.fa.bgz");
+        let mut writer = OutputWriter::create(&path, Compression::Bgzf, true).unwrap();
+        writer.write_all(&content).unwrap();
+        writer.finish().unwrap();
+
+        // 没有.fai时不使用索引
+        assert!(IndexedFasta::open(&path).unwrap().is_none());
+        write_fai(&build_index(&path).unwrap(), &format!("{}.fai", path)).unwrap();
+
+        let mut fasta = IndexedFasta::open(&path).unwrap().unwrap();
+        for (start, end) in [(0, 10), (65_270, 65_300), (130_000, 131_000), (199_990, 200_000)] {
+            assert_eq!(fasta.fetch("long", start, end).unwrap().0, bases[start..end]);
+        }
+    }
+
+    #[test]
+    fn test_duplicated_names_are_not_fetched() {
+        let path = write_temp("duplicated.fa", b">chr1\nACGT\n>chr2\nGGCC\n>chr1\nTTTT\n");
+        write_fai(&build_index(&path).unwrap(), &format!("{}.fai", path)).unwrap();
+
+        // 重复的序列名不保留第一条记录
+        let mut fasta = IndexedFasta::open(&path).unwrap().unwrap();
+        assert!(fasta.is_duplicated("chr1"));
+        assert_eq!(fasta.sequence_length("chr1"), None);
+        assert!(fasta.fetch("chr1", 0, 4).is_err());
+        assert!(!fasta.is_duplicated("chr2"));
+        assert_eq!(fasta.fetch("chr2", 1, 3).unwrap().0, "GC");
+    }
+}
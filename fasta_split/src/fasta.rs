@@ -1,83 +1,224 @@
-use std::fs::File;
-use std::io::{self, BufRead, BufReader};
+use crate::compress::open_input;
+use std::io::{self, BufRead};
 
 /// 代表一条FASTA序列的结构体
+/// 输入为FASTQ时，quality中保存与序列等长的碱基质量
 pub struct FastaSequence {
-    pub id: String,          // 序列标识符
-    pub description: String, // 序列描述信息
-    pub sequence: String,    // 序列本身
+    pub id: String,              // 序列标识符
+    pub description: String,     // 序列描述信息
+    pub sequence: String,        // 序列本身
+    pub quality: Option<String>, // 碱基质量（仅FASTQ）
 }
 
-/// 从指定文件路径读取并解析FASTA文件
-/// 返回一个包含所有FASTA序列的向量
-pub fn read_fasta(file_path: &str) -> Result<Vec<FastaSequence>, io::Error> {
-    let file = File::open(file_path)?;
-    let reader = BufReader::new(file); // 创建缓冲读取器
-
-    let mut sequences = Vec::new(); // 存储解析后的序列
-    let mut current_id = String::new();
-    let mut current_description = String::new();
-    let mut current_sequence = String::new();
-
-    for line in reader.lines() {
-        let line = line?; // 逐行读取
-        if line.starts_with('>') {
-            // 遇到新的序列头，保存之前的序列
-            if !current_id.is_empty() {
-                sequences.push(FastaSequence {
-                    id: current_id.clone(),
-                    description: current_description.clone(),
-                    sequence: current_sequence.clone(),
-                });
-                current_sequence.clear(); // 清空当前序列内容
-            }
-            // 解析序列头
-            let parts: Vec<&str> = line[1..].splitn(2, ' ').collect();
-            current_id = parts[0].to_string();
-            current_description = if parts.len() > 1 {
-                parts[1].to_string()
-            } else {
-                String::new()
+/// 输入文件的格式，由第一条记录的序列头自动识别
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SequenceFormat {
+    Fasta, // 以'>'开头
+    Fastq, // 以'@'开头
+}
+
+/// 流式序列读取器，每次迭代只解析并返回一条序列
+/// 自动识别FASTA和FASTQ格式；内存占用只取决于最长的单条序列，而不是整个文件
+pub struct FastaReader<R: BufRead> {
+    reader: R,
+    line: String,                   // 行缓冲区
+    next_header: Option<String>,    // 已读到但尚未处理的下一条序列头
+    format: Option<SequenceFormat>, // 识别出的文件格式
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+impl<R: BufRead> FastaReader<R> {
+    /// 基于任意缓冲读取器创建序列读取器
+    pub fn new(reader: R) -> Self {
+        FastaReader {
+            reader,
+            line: String::new(),
+            next_header: None,
+            format: None,
+        }
+    }
+
+    /// 读取下一行，去掉行尾换行符；到达文件末尾时返回false
+    fn read_line(&mut self) -> io::Result<bool> {
+        self.line.clear();
+        if self.reader.read_line(&mut self.line)? == 0 {
+            return Ok(false);
+        }
+        let trimmed_len = self.line.trim_end_matches(['\n', '\r']).len();
+        self.line.truncate(trimmed_len);
+        Ok(true)
+    }
+
+    /// 找到下一条序列头，返回去掉'>'或'@'后的内容
+    /// 第一条序列头决定文件格式；FASTA中第一条序列之前的内容会被跳过
+    fn find_header(&mut self) -> io::Result<Option<String>> {
+        while self.read_line()? {
+            let format = match self.line.as_bytes().first() {
+                Some(b'>') => Some(SequenceFormat::Fasta),
+                Some(b'@') => Some(SequenceFormat::Fastq),
+                _ => None,
             };
-        } else {
-            // 累加序列内容
-            current_sequence.push_str(&line);
+            match (self.format, format) {
+                (None, Some(format)) => self.format = Some(format),
+                (Some(current), Some(format)) if current == format => {}
+                (Some(SequenceFormat::Fastq), _) if !self.line.is_empty() => {
+                    return Err(invalid_data(format!("Invalid FASTQ record header: {}", self.line)));
+                }
+                _ => continue,
+            }
+            return Ok(Some(self.line[1..].to_string()));
+        }
+        Ok(None)
+    }
+
+    fn next_record(&mut self) -> io::Result<Option<FastaSequence>> {
+        let header = match self.next_header.take() {
+            Some(header) => header,
+            None => match self.find_header()? {
+                Some(header) => header,
+                None => return Ok(None),
+            },
+        };
+
+        // 解析序列头
+        let (id, description) = match header.split_once(' ') {
+            Some((id, description)) => (id.to_string(), description.to_string()),
+            None => (header, String::new()),
+        };
+
+        if self.format == Some(SequenceFormat::Fastq) {
+            return self.read_fastq_body(id, description).map(Some);
+        }
+
+        // 累加序列内容，直到遇到下一条序列头或文件末尾
+        let mut sequence = String::new();
+        while self.read_line()? {
+            if let Some(next_header) = self.line.strip_prefix('>') {
+                self.next_header = Some(next_header.to_string());
+                break;
+            }
+            sequence.push_str(&self.line);
+        }
+
+        Ok(Some(FastaSequence { id, description, sequence, quality: None }))
+    }
+
+    /// 读取FASTQ记录的序列和质量部分，支持多行FASTQ
+    fn read_fastq_body(&mut self, id: String, description: String) -> io::Result<FastaSequence> {
+        // 序列行一直到'+'分隔行为止
+        let mut sequence = String::new();
+        loop {
+            if !self.read_line()? {
+                return Err(invalid_data(format!("Truncated FASTQ record: {}", id)));
+            }
+            if self.line.starts_with('+') {
+                break;
+            }
+            sequence.push_str(&self.line);
+        }
+
+        // 质量行的总长度与序列长度相同
+        let mut quality = String::with_capacity(sequence.len());
+        while quality.len() < sequence.len() {
+            if !self.read_line()? {
+                return Err(invalid_data(format!("Truncated FASTQ record: {}", id)));
+            }
+            quality.push_str(&self.line);
         }
+        if quality.len() != sequence.len() {
+            return Err(invalid_data(format!("Sequence and quality lengths differ in FASTQ record: {}", id)));
+        }
+
+        Ok(FastaSequence { id, description, sequence, quality: Some(quality) })
     }
-    // 保存最后一条序列
-    if !current_id.is_empty() {
-        sequences.push(FastaSequence {
-            id: current_id,
-            description: current_description,
-            sequence: current_sequence,
-        });
+}
+
+impl<R: BufRead> Iterator for FastaReader<R> {
+    type Item = io::Result<FastaSequence>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.next_record() {
+                // 序列头中没有标识符（如单独的'>'）时无法命名输出，跳过整条序列
+                Ok(Some(sequence)) if sequence.id.is_empty() => continue,
+                result => return result.transpose(),
+            }
+        }
     }
+}
 
-    Ok(sequences) // 返回解析后的序列向量
+/// 打开指定路径的FASTA或FASTQ文件，返回流式读取器
+/// 支持纯文本、gzip和bgzip压缩的文件
+pub fn open_fasta(file_path: &str) -> io::Result<FastaReader<Box<dyn BufRead>>> {
+    Ok(FastaReader::new(open_input(file_path)?))
+}
+
+/// 从指定文件路径读取并解析FASTA文件
+/// 返回一个包含所有FASTA序列的向量
+pub fn read_fasta(file_path: &str) -> Result<Vec<FastaSequence>, io::Error> {
+    open_fasta(file_path)?.collect()
 }
 
-//#[cfg(test)]
-//mod tests {
-//    use super::*;
-
-//    #[test]
-//    fn test_read_fasta() {
-//        // 测试解析FASTA文件的功能
-//        let fasta_content = ">seq1 description
-//        ATCG
-//        >seq2 another description
-//        GCTA";
-//        let mut temp_file = tempfile::NamedTempFile::new().unwrap();
-//        use std::io::Write;
-//        write!(temp_file, "{}", fasta_content).unwrap();
-
-//        let sequences = read_fasta(temp_file.path().to_str().unwrap()).unwrap();
-//        assert_eq!(sequences.len(), 2);
-//        assert_eq!(sequences[0].id, "seq1");
-//        assert_eq!(sequences[0].description, "description");
-//        assert_eq!(sequences[0].sequence, "ATCG");
-//        assert_eq!(sequences[1].id, "seq2");
-//        assert_eq!(sequences[1].description, "another description");
-//        assert_eq!(sequences[1].sequence, "GCTA");
-//    }
-//}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::write_temp;
+    use std::io::Cursor;
+
+    fn parse(content: &str) -> io::Result<Vec<FastaSequence>> {
+        FastaReader::new(Cursor::new(content.as_bytes().to_vec())).collect()
+    }
+
+    #[test]
+    fn test_read_fasta() {
+        // 测试解析FASTA文件的功能
+        let fasta_content = ">seq1 description\nATCG\n>seq2 another description\nGC\nTA\n";
+        let path = write_temp("read.fa", fasta_content.as_bytes());
+
+        let sequences = read_fasta(&path).unwrap();
+        assert_eq!(sequences.len(), 2);
+        assert_eq!(sequences[0].id, "seq1");
+        assert_eq!(sequences[0].description, "description");
+        assert_eq!(sequences[0].sequence, "ATCG");
+        assert_eq!(sequences[1].id, "seq2");
+        assert_eq!(sequences[1].description, "another description");
+        assert_eq!(sequences[1].sequence, "GCTA");
+        assert!(sequences[1].quality.is_none());
+    }
+
+    #[test]
+    fn test_records_without_id_are_skipped() {
+        let sequences = parse(">\nAAAA\n>seq1\nCG\n> description only\nTT\n").unwrap();
+        assert_eq!(sequences.len(), 1);
+        assert_eq!(sequences[0].id, "seq1");
+        assert_eq!(sequences[0].sequence, "CG");
+
+        let sequences = parse("@\nACGT\n+\nIIII\n@r1\nAC\n+\nII\n").unwrap();
+        assert_eq!(sequences.len(), 1);
+        assert_eq!(sequences[0].id, "r1");
+    }
+
+    #[test]
+    fn test_read_fastq() {
+        // 单行和多行FASTQ，质量行以'@'开头也不会被误认为序列头
+        let sequences = parse("@r1 lane1\nACGT\n+\nIIII\n@r2\nAC\nGTA\n+r2\n@@I\nII\n").unwrap();
+        assert_eq!(sequences.len(), 2);
+        assert_eq!(sequences[0].id, "r1");
+        assert_eq!(sequences[0].description, "lane1");
+        assert_eq!(sequences[0].sequence, "ACGT");
+        assert_eq!(sequences[0].quality.as_deref(), Some("IIII"));
+        assert_eq!(sequences[1].sequence, "ACGTA");
+        assert_eq!(sequences[1].quality.as_deref(), Some("@@III"));
+    }
+
+    #[test]
+    fn test_read_fastq_errors() {
+        assert!(parse("@r1\nACGT\n+\nIII").is_err());
+        assert!(parse("@r1\nACGT\n").is_err());
+        assert!(parse("@r1\nACGT\n+\nIIIII\n").is_err());
+        assert!(parse("@r1\nACGT\n+\nIIII\n>seq\nACGT\n").is_err());
+    }
+}
@@ -0,0 +1,141 @@
+use crate::agp::{AgpComponent, AgpRecord};
+use crate::fasta::FastaSequence;
+use seq_utils::reverse_complement;
+use std::collections::{HashMap, HashSet};
+use std::io;
+
+/// 根据AGP记录把contig拼接成scaffold
+/// 方向为'-'的组分取反向互补，gap按指定长度填充N
+/// 标识符重复的contig无法确定使用哪一条，被AGP引用时返回错误
+/// 返回拼接出的scaffold列表，以及AGP中未使用的contig标识符
+pub fn join_by_agp(contigs: &[FastaSequence], records: &[AgpRecord]) -> io::Result<(Vec<FastaSequence>, Vec<String>)> {
+    let mut contig_index: HashMap<&str, &FastaSequence> = HashMap::with_capacity(contigs.len());
+    let mut duplicated = HashSet::new();
+    for contig in contigs {
+        if contig_index.insert(contig.id.as_str(), contig).is_some() {
+            duplicated.insert(contig.id.as_str());
+        }
+    }
+    let mut used = HashSet::new();
+    let mut scaffolds: Vec<FastaSequence> = Vec::new();
+
+    for record in records {
+        // 同一对象的记录是连续的，遇到新对象时开始一条新的scaffold
+        if scaffolds.last().map_or(true, |scaffold| scaffold.id != record.object) {
+            scaffolds.push(FastaSequence {
+                id: record.object.clone(),
+                description: String::new(),
+                sequence: String::new(),
+                quality: None,
+            });
+        }
+        let scaffold = scaffolds.last_mut().expect("pushed above");
+
+        match &record.component {
+            AgpComponent::Gap { length } => {
+                scaffold.sequence.push_str(&"N".repeat(*length));
+            }
+            AgpComponent::Sequence { id, start, end, reverse } => {
+                if duplicated.contains(id.as_str()) {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("AGP line {}: component {} appears more than once in contigs", record.line_number, id),
+                    ));
+                }
+                let contig = contig_index.get(id.as_str()).ok_or_else(|| io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("AGP line {}: component {} not found in contigs", record.line_number, id),
+                ))?;
+                if *end > contig.sequence.len() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("AGP line {}: component_end {} exceeds length {} of {}", record.line_number, end, contig.sequence.len(), id),
+                    ));
+                }
+                used.insert(id.as_str());
+
+                let part = &contig.sequence[start - 1..*end];
+                if *reverse {
+                    scaffold.sequence.push_str(&reverse_complement(part));
+                } else {
+                    scaffold.sequence.push_str(part);
+                }
+            }
+        }
+    }
+
+    let unused = contigs.iter()
+        .filter(|contig| !used.contains(contig.id.as_str()))
+        .map(|contig| contig.id.clone())
+        .collect();
+
+    Ok((scaffolds, unused))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agp::{read_agp, write_agp, write_agp_header, AgpOptions};
+    use crate::split::{split_sequence, BreakRule, SplitOptions};
+    use crate::test_utils::write_temp;
+
+    fn sequence(id: &str, bases: &str) -> FastaSequence {
+        FastaSequence { id: id.to_string(), description: String::new(), sequence: bases.to_string(), quality: None }
+    }
+
+    #[test]
+    fn test_split_then_join_restores_scaffold() {
+        // splitN输出的contig和AGP经joinAgp拼接后与原scaffold相同（两端的N除外）
+        let scaffold = sequence("scaf1", "NNACGTACNNNNNGGTTNNNNNNNNNNCCAN");
+        let options = SplitOptions { break_rule: BreakRule::N, min_gap: 1, min_length: 0, keep_short: false };
+        let (contigs, positions) = split_sequence(&scaffold, &options);
+        let agp_options = AgpOptions {
+            gap_type: "scaffold".to_string(),
+            linkage: true,
+            linkage_evidence: "paired-ends".to_string(),
+            unknown_gap_length: Some(10),
+        };
+        let mut content = Vec::new();
+        write_agp_header(&mut content).unwrap();
+        write_agp(&mut content, &positions, &agp_options).unwrap();
+        let records = read_agp(&write_temp("join.agp", &content)).unwrap();
+
+        let (scaffolds, unused) = join_by_agp(&contigs, &records).unwrap();
+        assert_eq!(scaffolds.len(), 1);
+        assert_eq!(scaffolds[0].id, "scaf1");
+        assert_eq!(scaffolds[0].sequence, scaffold.sequence.trim_matches('N'));
+        assert!(unused.is_empty());
+    }
+
+    #[test]
+    fn test_join_reverse_component_and_unused_contigs() {
+        let contigs = vec![sequence("ctg1", "AACCGG"), sequence("ctg2", "TTTAC"), sequence("ctg3", "GGG")];
+        let path = write_temp("reverse.agp", b"scaf1\t1\t4\t1\tW\tctg1\t2\t5\t+\nscaf1\t5\t7\t2\tN\t3\tscaffold\tyes\tmap\nscaf1\t8\t12\t3\tW\tctg2\t1\t5\t-\n");
+        let (scaffolds, unused) = join_by_agp(&contigs, &read_agp(&path).unwrap()).unwrap();
+        assert_eq!(scaffolds[0].sequence, "ACCGNNNGTAAA");
+        assert_eq!(unused, vec!["ctg3".to_string()]);
+    }
+
+    #[test]
+    fn test_join_reports_missing_and_short_components() {
+        let contigs = vec![sequence("ctg1", "ACGT")];
+        let path = write_temp("missing.agp", b"scaf1\t1\t4\t1\tW\tctg9\t1\t4\t+\n");
+        assert!(join_by_agp(&contigs, &read_agp(&path).unwrap()).is_err());
+        let path = write_temp("too_long.agp", b"scaf1\t1\t5\t1\tW\tctg1\t1\t5\t+\n");
+        assert!(join_by_agp(&contigs, &read_agp(&path).unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_join_rejects_duplicated_contigs() {
+        let contigs = vec![sequence("ctg1", "ACGT"), sequence("ctg2", "GG"), sequence("ctg1", "TTTT")];
+        let path = write_temp("duplicated.agp", b"scaf1\t1\t2\t1\tW\tctg2\t1\t2\t+\nscaf1\t3\t6\t2\tW\tctg1\t1\t4\t+\n");
+        let error = join_by_agp(&contigs, &read_agp(&path).unwrap()).err().unwrap();
+        assert!(error.to_string().contains("appears more than once"));
+
+        // 未被引用的重复contig不影响拼接
+        let path = write_temp("unique.agp", b"scaf1\t1\t2\t1\tW\tctg2\t1\t2\t+\n");
+        let (scaffolds, unused) = join_by_agp(&contigs, &read_agp(&path).unwrap()).unwrap();
+        assert_eq!(scaffolds[0].sequence, "GG");
+        assert_eq!(unused, vec!["ctg1".to_string(), "ctg1".to_string()]);
+    }
+}
@@ -4,24 +4,38 @@ mod split;
 mod split_bed;
 mod output;
 mod bed;
+mod compress;
+mod faidx;
+mod agp;
+mod join_agp;
+mod split_gff;
+mod split_window;
+mod split_parts;
+#[cfg(test)]
+mod test_utils;
 
-use clap::{Arg, Command};
+use clap::{Arg, ArgAction, ArgGroup, ArgMatches, Command};
+use compress::Compression;
+use seq_utils::gff;
+use std::collections::HashSet;
+use std::io;
+use std::path::Path;
 use std::process;
 
 fn main() {
     let matches = Command::new("FASTA Split Processor")
         .version("1.0.0")
         .author("WangZhSi")
-        .about("\nSplit FASTA file, by Ns or input bed;")
+        .about("\nSplit FASTA file, by Ns, input bed, GFF3/GTF, fixed windows or into part files; join contigs by AGP;")
         .subcommand(
             Command::new("splitN")
-                .about("Splits sequences based on N positions")
+                .about("Splits sequences at runs of N, soft-masked, IUPAC or regex-matched bases")
                 .arg(
                     Arg::new("fasta")
                         .short('f')
                         .long("fasta")
                         .value_name("FILE")
-                        .help("Path to the input FASTA file")
+                        .help("Path to the input FASTA or FASTQ file")
                         .required(true),
                 )
                 .arg(
@@ -47,7 +61,91 @@ fn main() {
                         .value_name("FILE")
                         .help("Path to the output sequences file")
                         .required(true),
-                ),
+                )
+                .arg(
+                    Arg::new("break_on")
+                        .long("break-on")
+                        .value_name("RULE")
+                        .value_parser(["N", "lowercase", "iupac", "regex"])
+                        .default_value("N")
+                        .help("What to split at: runs of N, lowercase (soft-masked) bases, IUPAC ambiguity codes, or matches of --break-regex"),
+                )
+                .arg(
+                    Arg::new("break_regex")
+                        .long("break-regex")
+                        .value_name("PATTERN")
+                        .required_if_eq("break_on", "regex")
+                        .help("Regular expression whose matches are split out, used with --break-on regex"),
+                )
+                .arg(
+                    Arg::new("min_gap")
+                        .long("min-gap")
+                        .value_name("INT")
+                        .value_parser(clap::value_parser!(usize))
+                        .default_value("1")
+                        .help("Minimum length of a break run to be used as a break point; shorter runs stay inside the fragment"),
+                )
+                .arg(
+                    Arg::new("min_length")
+                        .long("min-length")
+                        .value_name("INT")
+                        .value_parser(clap::value_parser!(usize))
+                        .default_value("0")
+                        .help("Minimum fragment length; shorter fragments are handled by --short-fragments"),
+                )
+                .arg(
+                    Arg::new("short_fragments")
+                        .long("short-fragments")
+                        .value_name("ACTION")
+                        .value_parser(["drop", "report"])
+                        .default_value("drop")
+                        .help("Drop fragments shorter than --min-length, or keep them and mark them as short in the positions log"),
+                )
+                .arg(
+                    Arg::new("output_summary")
+                        .short('m')
+                        .long("output-summary")
+                        .value_name("FILE")
+                        .help("Path to an optional assembly summary (N50/L50, N90/L90, auN, GC, gaps by --break-on) at scaffold and contig level"),
+                )
+                .arg(
+                    Arg::new("output_agp")
+                        .short('a')
+                        .long("output-agp")
+                        .value_name("FILE")
+                        .help("Path to an optional AGP 2.1 file describing the scaffold-to-contig layout; only with --break-on N and without dropping short fragments"),
+                )
+                .arg(
+                    Arg::new("agp_gap_type")
+                        .long("agp-gap-type")
+                        .value_name("TYPE")
+                        .default_value("scaffold")
+                        .help("AGP gap type for N runs (scaffold, contig, centromere, short_arm, heterochromatin, telomere, repeat, contamination)"),
+                )
+                .arg(
+                    Arg::new("agp_linkage")
+                        .long("agp-linkage")
+                        .value_name("yes|no")
+                        .value_parser(["yes", "no"])
+                        .default_value("yes")
+                        .help("AGP linkage column for gaps"),
+                )
+                .arg(
+                    Arg::new("agp_evidence")
+                        .long("agp-evidence")
+                        .value_name("EVIDENCE")
+                        .default_value("paired-ends")
+                        .help("AGP linkage evidence for gaps, several separated by ';' (na when linkage is no)"),
+                )
+                .arg(
+                    Arg::new("agp_unknown_length")
+                        .long("agp-unknown-length")
+                        .value_name("INT")
+                        .value_parser(clap::value_parser!(usize))
+                        .help("Write gaps of exactly this length as U (unknown size) lines, e.g. 100"),
+                )
+                .arg(compress_arg())
+                .arg(gzi_arg()),
         )
         .subcommand(
             Command::new("splitBed")
@@ -57,7 +155,7 @@ fn main() {
                         .short('f')
                         .long("fasta")
                         .value_name("FILE")
-                        .help("Path to the input FASTA file")
+                        .help("Path to the input FASTA or FASTQ file")
                         .required(true),
                 )
                 .arg(
@@ -65,9 +163,63 @@ fn main() {
                         .short('b')
                         .long("bed")
                         .value_name("FILE")
-                        .help("Path to the BED file(0 base, [close, open) ); default for 3 column; 4th column will used as seq id;")
+                        .help("Path to the BED file(0 base, [close, open) ); default for 3 column; 4th column will used as seq id; 6th column strand, - will be reverse complemented; BED12 blocks will be joined;")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("output_seqs")
+                        .short('q')
+                        .long("output-seqs")
+                        .value_name("FILE")
+                        .help("Path to the output sequences file")
                         .required(true),
                 )
+                .arg(
+                    Arg::new("cds_only")
+                        .long("cds-only")
+                        .action(ArgAction::SetTrue)
+                        .help("Only extract the thickStart-thickEnd (coding) part of each record"),
+                )
+                .arg(group_arg())
+                .arg(compress_arg())
+                .arg(gzi_arg()),
+        )
+        .subcommand(
+            Command::new("splitGff")
+                .about("Extracts features from a GFF3 or GTF annotation")
+                .arg(
+                    Arg::new("fasta")
+                        .short('f')
+                        .long("fasta")
+                        .value_name("FILE")
+                        .help("Path to the input genome FASTA file")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("gff")
+                        .short('g')
+                        .long("gff")
+                        .value_name("FILE")
+                        .help("Path to the GFF3 or GTF annotation file")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("types")
+                        .short('t')
+                        .long("types")
+                        .value_name("TYPES")
+                        .value_delimiter(',')
+                        .default_value("mRNA")
+                        .help("Feature types to extract, comma separated (gene, mRNA, CDS, exon, five_prime_UTR, ...)"),
+                )
+                .arg(
+                    Arg::new("mrna_parts")
+                        .long("mrna-parts")
+                        .value_name("TYPE")
+                        .value_parser(["exon", "CDS"])
+                        .default_value("exon")
+                        .help("Child features joined to build each mRNA/transcript sequence"),
+                )
                 .arg(
                     Arg::new("output_seqs")
                         .short('q')
@@ -75,6 +227,189 @@ fn main() {
                         .value_name("FILE")
                         .help("Path to the output sequences file")
                         .required(true),
+                )
+                .arg(group_arg())
+                .arg(compress_arg())
+                .arg(gzi_arg()),
+        )
+        .subcommand(
+            Command::new("splitWindow")
+                .about("Cuts sequences into fixed-size, optionally overlapping windows")
+                .arg(
+                    Arg::new("fasta")
+                        .short('f')
+                        .long("fasta")
+                        .value_name("FILE")
+                        .help("Path to the input FASTA or FASTQ file")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("window")
+                        .short('w')
+                        .long("window")
+                        .value_name("INT")
+                        .value_parser(clap::value_parser!(usize))
+                        .help("Window size; the last window of a sequence may be shorter")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("step")
+                        .long("step")
+                        .value_name("INT")
+                        .value_parser(clap::value_parser!(usize))
+                        .conflicts_with("overlap")
+                        .help("Distance between the starts of neighbouring windows [default: window size]"),
+                )
+                .arg(
+                    Arg::new("overlap")
+                        .long("overlap")
+                        .value_name("INT")
+                        .value_parser(clap::value_parser!(usize))
+                        .help("Number of bases shared by neighbouring windows, the same as --step window-overlap"),
+                )
+                .arg(
+                    Arg::new("max_n")
+                        .long("max-n")
+                        .value_name("FRACTION")
+                        .value_parser(clap::value_parser!(f64))
+                        .help("Skip windows whose fraction of N is above this value, e.g. 0.5"),
+                )
+                .arg(
+                    Arg::new("output_seqs")
+                        .short('q')
+                        .long("output-seqs")
+                        .value_name("FILE")
+                        .help("Path to the output window sequences file")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("output_bed")
+                        .short('b')
+                        .long("output-bed")
+                        .value_name("FILE")
+                        .help("Path to the output BED of window coordinates; windows skipped by --max-n are left out")
+                        .required(true),
+                )
+                .arg(compress_arg())
+                .arg(gzi_arg()),
+        )
+        .subcommand(
+            Command::new("splitParts")
+                .about("Splits the input into several part files or one file per sequence")
+                .arg(
+                    Arg::new("fasta")
+                        .short('f')
+                        .long("fasta")
+                        .value_name("FILE")
+                        .help("Path to the input FASTA or FASTQ file")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("output_dir")
+                        .short('o')
+                        .long("output-dir")
+                        .value_name("DIR")
+                        .help("Directory for the part files, created if missing")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("parts")
+                        .short('n')
+                        .long("parts")
+                        .value_name("INT")
+                        .value_parser(clap::value_parser!(usize))
+                        .help("Number of part files, balanced by total bases"),
+                )
+                .arg(
+                    Arg::new("max_records")
+                        .long("max-records")
+                        .value_name("INT")
+                        .value_parser(clap::value_parser!(usize))
+                        .help("Start a new part file after this many sequences"),
+                )
+                .arg(
+                    Arg::new("max_bases")
+                        .long("max-bases")
+                        .value_name("INT")
+                        .value_parser(clap::value_parser!(u64))
+                        .help("Start a new part file before exceeding this many bases"),
+                )
+                .arg(
+                    Arg::new("per_record")
+                        .long("per-record")
+                        .action(ArgAction::SetTrue)
+                        .help("Write every sequence to its own file"),
+                )
+                .group(
+                    ArgGroup::new("mode")
+                        .args(["parts", "max_records", "max_bases", "per_record"])
+                        .multiple(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("template")
+                        .short('t')
+                        .long("template")
+                        .value_name("TEMPLATE")
+                        .help("File name template with {stem}, {index} and {id} [default: {stem}.part_{index}.fa, or {id}.fa with --per-record; .fq for FASTQ input]"),
+                )
+                .arg(
+                    Arg::new("manifest")
+                        .short('m')
+                        .long("manifest")
+                        .value_name("FILE")
+                        .help("Path to the manifest of part files [default: <output-dir>/manifest.tsv]"),
+                )
+                .arg(compress_arg())
+                .arg(gzi_arg()),
+        )
+        .subcommand(
+            Command::new("joinAgp")
+                .about("Builds scaffolds from contigs and an AGP file")
+                .arg(
+                    Arg::new("fasta")
+                        .short('f')
+                        .long("fasta")
+                        .value_name("FILE")
+                        .help("Path to the input contigs FASTA file")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("agp")
+                        .short('a')
+                        .long("agp")
+                        .value_name("FILE")
+                        .help("Path to the AGP file describing the scaffold layout")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("output_seqs")
+                        .short('q')
+                        .long("output-seqs")
+                        .value_name("FILE")
+                        .help("Path to the output scaffolds file")
+                        .required(true),
+                )
+                .arg(compress_arg())
+                .arg(gzi_arg()),
+        )
+        .subcommand(
+            Command::new("faidx")
+                .about("Builds a samtools-compatible .fai index")
+                .arg(
+                    Arg::new("fasta")
+                        .short('f')
+                        .long("fasta")
+                        .value_name("FILE")
+                        .help("Path to the input FASTA file (plain or bgzip)")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .value_name("FILE")
+                        .help("Path to the output index; default <FASTA>.fai"),
                 ),
         )
         .get_matches();
@@ -85,98 +420,532 @@ fn main() {
             let output_stats = sub_m.get_one::<String>("output_stats").expect("required").clone();
             let output_positions = sub_m.get_one::<String>("output_positions").expect("required").clone();
             let output_seqs = sub_m.get_one::<String>("output_seqs").expect("required").clone();
+            let outputs = SplitNOutputs {
+                compression: output_compression(sub_m, &output_seqs),
+                write_gzi: sub_m.get_flag("gzi"),
+                stats: output_stats,
+                positions: output_positions,
+                seqs: output_seqs,
+                agp: sub_m.get_one::<String>("output_agp").cloned(),
+                summary: sub_m.get_one::<String>("output_summary").cloned(),
+            };
+            check_gzi(&outputs.seqs, outputs.compression, outputs.write_gzi);
+            let break_rule = match sub_m.get_one::<String>("break_on").expect("has default").as_str() {
+                "lowercase" => split::BreakRule::Lowercase,
+                "iupac" => split::BreakRule::Iupac,
+                "regex" => {
+                    let pattern = sub_m.get_one::<String>("break_regex").expect("required with --break-on regex");
+                    split::BreakRule::Regex(exit_on_error(
+                        regex::Regex::new(pattern).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e)),
+                        "Invalid --break-regex",
+                    ))
+                }
+                _ => split::BreakRule::N,
+            };
+            let options = split::SplitOptions {
+                break_rule,
+                min_gap: *sub_m.get_one::<usize>("min_gap").expect("has default"),
+                min_length: *sub_m.get_one::<usize>("min_length").expect("has default"),
+                keep_short: sub_m.get_one::<String>("short_fragments").expect("has default") == "report",
+            };
+            let agp_options = agp::AgpOptions {
+                gap_type: sub_m.get_one::<String>("agp_gap_type").expect("has default").clone(),
+                linkage: sub_m.get_one::<String>("agp_linkage").expect("has default") == "yes",
+                linkage_evidence: sub_m.get_one::<String>("agp_evidence").expect("has default").clone(),
+                unknown_gap_length: sub_m.get_one::<usize>("agp_unknown_length").copied(),
+            };
+            if let Err(e) = agp_options.validate() {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+            // 其他规则切除的是真实序列，写成AGP的gap行后无法再用joinAgp还原
+            if outputs.agp.is_some() && !matches!(options.break_rule, split::BreakRule::N) {
+                eprintln!("--output-agp requires --break-on N; {} breaks remove real sequence that AGP gap lines would replace with N", options.break_rule.name());
+                process::exit(1);
+            }
+            // 被丢弃的短片段在AGP中只能写成gap，joinAgp还原时其碱基会变成N，对象坐标也不再与原scaffold一致
+            if outputs.agp.is_some() && options.min_length > 0 && !options.keep_short {
+                eprintln!("--output-agp cannot describe fragments dropped by --min-length; use --short-fragments report to keep them");
+                process::exit(1);
+            }
 
             // 调用基于N的切割功能
-            run_split_n(&input_fasta, &output_stats, &output_positions, &output_seqs);
+            run_split_n(&input_fasta, &outputs, &options, &agp_options);
         },
         Some(("splitBed", sub_m)) => {
             let input_fasta = sub_m.get_one::<String>("fasta").expect("required").clone();
             let bed_file = sub_m.get_one::<String>("bed").expect("required").clone();
             let output_seqs = sub_m.get_one::<String>("output_seqs").expect("required").clone();
+            let cds_only = sub_m.get_flag("cds_only");
+            let outputs = region_outputs(sub_m, output_seqs);
 
             // 调用基于BED文件的切割功能
-            run_split_bed(&input_fasta, &bed_file, &output_seqs);
+            run_split_bed(&input_fasta, &bed_file, cds_only, &outputs);
+        },
+        Some(("splitGff", sub_m)) => {
+            let input_fasta = sub_m.get_one::<String>("fasta").expect("required").clone();
+            let gff_file = sub_m.get_one::<String>("gff").expect("required").clone();
+            let types: Vec<String> = sub_m.get_many::<String>("types").expect("has default").cloned().collect();
+            let part_type = sub_m.get_one::<String>("mrna_parts").expect("has default").clone();
+            let output_seqs = sub_m.get_one::<String>("output_seqs").expect("required").clone();
+            let outputs = region_outputs(sub_m, output_seqs);
+
+            // 调用基于GFF文件的提取功能
+            run_split_gff(&input_fasta, &gff_file, &types, &part_type, &outputs);
+        },
+        Some(("splitWindow", sub_m)) => {
+            let input_fasta = sub_m.get_one::<String>("fasta").expect("required").clone();
+            let output_seqs = sub_m.get_one::<String>("output_seqs").expect("required").clone();
+            let output_bed = sub_m.get_one::<String>("output_bed").expect("required").clone();
+            let compression = output_compression(sub_m, &output_seqs);
+            let write_gzi = sub_m.get_flag("gzi");
+            check_gzi(&output_seqs, compression, write_gzi);
+            let window = *sub_m.get_one::<usize>("window").expect("required");
+            let step = match (sub_m.get_one::<usize>("step"), sub_m.get_one::<usize>("overlap")) {
+                (Some(&step), _) => step,
+                (None, Some(&overlap)) => window.saturating_sub(overlap),
+                (None, None) => window,
+            };
+            let options = split_window::WindowOptions {
+                window,
+                step,
+                max_n_fraction: sub_m.get_one::<f64>("max_n").copied(),
+            };
+            if let Err(e) = options.validate() {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+
+            // 调用按固定窗口切割的功能
+            run_split_window(&input_fasta, &output_seqs, &output_bed, &options, compression, write_gzi);
+        },
+        Some(("splitParts", sub_m)) => {
+            let input_fasta = sub_m.get_one::<String>("fasta").expect("required").clone();
+            let output_dir = sub_m.get_one::<String>("output_dir").expect("required").clone();
+            let parts = sub_m.get_one::<usize>("parts").copied();
+            let max_records = sub_m.get_one::<usize>("max_records").copied();
+            let max_bases = sub_m.get_one::<u64>("max_bases").copied();
+            let per_record = sub_m.get_flag("per_record");
+
+            // 三种拆分方式只能选一种，记录数和碱基数上限可以同时使用
+            let limits = max_records.is_some() || max_bases.is_some();
+            let mode = match (parts, limits, per_record) {
+                (Some(parts), false, false) if parts > 0 => split_parts::PartMode::Balanced(parts),
+                (None, true, false) if max_records != Some(0) && max_bases != Some(0) =>
+                    split_parts::PartMode::Limits { max_records, max_bases },
+                (None, false, true) => split_parts::PartMode::PerRecord,
+                _ => {
+                    eprintln!("Use one of --parts, --max-records/--max-bases or --per-record, with values greater than 0");
+                    process::exit(1);
+                }
+            };
+            let template = sub_m.get_one::<String>("template").cloned();
+            let manifest = sub_m.get_one::<String>("manifest").cloned()
+                .unwrap_or_else(|| Path::new(&output_dir).join("manifest.tsv").to_string_lossy().into_owned());
+            let compression = sub_m.get_one::<String>("compress")
+                .map(|name| Compression::from_name(name).expect("validated by clap"));
+            let write_gzi = sub_m.get_flag("gzi");
+
+            // 调用拆分成多个文件的功能
+            run_split_parts(&input_fasta, &output_dir, &mode, template.as_deref(), &manifest, compression, write_gzi);
+        },
+        Some(("joinAgp", sub_m)) => {
+            let input_fasta = sub_m.get_one::<String>("fasta").expect("required").clone();
+            let agp_file = sub_m.get_one::<String>("agp").expect("required").clone();
+            let output_seqs = sub_m.get_one::<String>("output_seqs").expect("required").clone();
+            let compression = output_compression(sub_m, &output_seqs);
+            let write_gzi = sub_m.get_flag("gzi");
+            check_gzi(&output_seqs, compression, write_gzi);
+
+            // 调用基于AGP文件的拼接功能
+            run_join_agp(&input_fasta, &agp_file, &output_seqs, compression, write_gzi);
+        },
+        Some(("faidx", sub_m)) => {
+            let input_fasta = sub_m.get_one::<String>("fasta").expect("required").clone();
+            let output = sub_m.get_one::<String>("output").cloned().unwrap_or_else(|| format!("{}.fai", input_fasta));
+
+            // 生成FASTA索引
+            run_faidx(&input_fasta, &output);
         },
         _ => {
-            eprintln!("Invalid subcommand. Use 'splitN' or 'splitBed'.");
+            eprintln!("Invalid subcommand. Use 'splitN', 'splitBed', 'splitGff', 'splitWindow', 'splitParts', 'joinAgp' or 'faidx'.");
             process::exit(1);
         }
     }
 }
 
-fn run_split_n(input_fasta: &str, output_stats: &str, output_positions: &str, output_seqs: &str) {
-    // 读取FASTA文件
-    let sequences = match fasta::read_fasta(input_fasta) {
-        Ok(seq) => seq,
+/// splitN的输出文件设置
+struct SplitNOutputs {
+    stats: String,              // 统计结果
+    positions: String,          // 切割位置记录
+    seqs: String,               // 切割后的序列
+    agp: Option<String>,        // 可选的AGP文件
+    summary: Option<String>,    // 可选的组装统计汇总
+    compression: Compression,   // 序列文件的压缩方式
+    write_gzi: bool,            // 是否为BGZF序列文件生成.gzi索引
+}
+
+/// splitBed和splitGff的输出设置
+struct RegionOutputs {
+    seqs: String,               // 提取出的序列
+    compression: Compression,   // 序列文件的压缩方式
+    write_gzi: bool,            // 是否为BGZF序列文件生成.gzi索引
+    group_by_chrom: bool,       // 有.fai索引时是否按染色体分组提取
+}
+
+/// 从命令行参数中读取splitBed和splitGff的输出设置
+fn region_outputs(sub_m: &ArgMatches, seqs: String) -> RegionOutputs {
+    let outputs = RegionOutputs {
+        compression: output_compression(sub_m, &seqs),
+        write_gzi: sub_m.get_flag("gzi"),
+        group_by_chrom: sub_m.get_flag("group_by_chrom"),
+        seqs,
+    };
+    check_gzi(&outputs.seqs, outputs.compression, outputs.write_gzi);
+    outputs
+}
+
+/// 输出序列文件的压缩方式参数
+fn compress_arg() -> Arg {
+    Arg::new("compress")
+        .long("compress")
+        .value_name("METHOD")
+        .value_parser(["gzip", "bgzf", "none"])
+        .help("Compression of the output sequences file; guessed from its extension (.gz, .bgz) by default")
+}
+
+/// 是否按染色体分组提取区间
+fn group_arg() -> Arg {
+    Arg::new("group_by_chrom")
+        .long("group-by-chrom")
+        .action(ArgAction::SetTrue)
+        .help("With a .fai index, fetch the regions of each sequence together instead of in BED order; without an index sequences are always streamed one at a time")
+}
+
+/// 是否为BGZF输出生成.gzi索引
+fn gzi_arg() -> Arg {
+    Arg::new("gzi")
+        .long("gzi")
+        .action(ArgAction::SetTrue)
+        .help("Also write a .gzi index next to BGZF compressed output sequences")
+}
+
+/// 解析输出序列文件的压缩方式，未指定时根据扩展名推测
+fn output_compression(sub_m: &ArgMatches, output_seqs: &str) -> Compression {
+    match sub_m.get_one::<String>("compress") {
+        Some(name) => Compression::from_name(name).expect("validated by clap"),
+        None => Compression::from_path(output_seqs),
+    }
+}
+
+/// 在创建任何输出文件之前检查--gzi是否可用，不可用时输出错误信息并退出程序
+fn check_gzi(output_seqs: &str, compression: Compression, write_gzi: bool) {
+    if let Err(e) = compress::check_gzi(output_seqs, compression, write_gzi) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+/// 遇到IO错误时输出错误信息并退出程序
+fn exit_on_error<T>(result: io::Result<T>, message: &str) -> T {
+    match result {
+        Ok(value) => value,
         Err(e) => {
-            eprintln!("Error reading FASTA file: {}", e);
+            eprintln!("{}: {}", message, e);
             process::exit(1);
         }
-    };
+    }
+}
+
+fn run_split_n(input_fasta: &str, outputs: &SplitNOutputs, options: &split::SplitOptions, agp_options: &agp::AgpOptions) {
+    // 打开FASTA文件，逐条读取序列
+    let reader = exit_on_error(fasta::open_fasta(input_fasta), "Error reading FASTA file");
 
-    // 统计序列信息
-    let stats = stats::calculate_stats(&sequences);
+    // 创建输出文件并写入表头
+    let mut stats_writer = exit_on_error(output::create_output(&outputs.stats, Compression::None, false), "Error writing stats file");
+    let mut positions_writer = exit_on_error(output::create_output(&outputs.positions, Compression::None, false), "Error writing split positions file");
+    let mut seqs_writer = exit_on_error(output::create_output(&outputs.seqs, outputs.compression, outputs.write_gzi), "Error writing new sequences file");
+    let mut agp_writer = outputs.agp.as_ref()
+        .map(|path| exit_on_error(output::create_output(path, Compression::None, false), "Error writing AGP file"));
+    exit_on_error(output::write_stats_header(&mut stats_writer), "Error writing stats file");
+    exit_on_error(output::write_split_positions_header(&mut positions_writer), "Error writing split positions file");
+    if let Some(writer) = agp_writer.as_mut() {
+        exit_on_error(agp::write_agp_header(writer), "Error writing AGP file");
+    }
 
-    // 切割序列
-    let (new_sequences, split_positions) = split::split_sequences(&sequences);
+    let mut scaffold_stats = stats::AssemblyStats::new();
+    let mut contig_stats = stats::AssemblyStats::new();
 
-    // 输出统计结果
-    if let Err(e) = output::write_stats(&stats, output_stats) {
-        eprintln!("Error writing stats file: {}", e);
-        process::exit(1);
+    for record in reader {
+        let sequence = exit_on_error(record, "Error reading FASTA file");
+
+        // 切割序列
+        let (new_sequences, split_positions) = split::split_sequence(&sequence, options);
+
+        // 统计序列信息并输出
+        let stats = stats::calculate_stats(&sequence, &split_positions, options.break_rule.name());
+        exit_on_error(output::write_stats(&mut stats_writer, &stats), "Error writing stats file");
+
+        // 累加scaffold和contig两个层面的组装统计；gap按所选切割规则计算，contig内部残留的区间都计为gap
+        scaffold_stats.add(&sequence.sequence, &options.break_rule, options.min_gap);
+        for contig in &new_sequences {
+            contig_stats.add(&contig.sequence, &options.break_rule, 1);
+        }
+
+        // 输出切割位置记录
+        exit_on_error(output::write_split_positions(&mut positions_writer, &split_positions), "Error writing split positions file");
+
+        // 输出AGP记录
+        if let Some(writer) = agp_writer.as_mut() {
+            exit_on_error(agp::write_agp(writer, &split_positions, agp_options), "Error writing AGP file");
+        }
+
+        // 输出新的FASTA序列
+        exit_on_error(output::write_new_sequences(&mut seqs_writer, &new_sequences), "Error writing new sequences file");
     }
 
-    // 输出切割位置记录
-    if let Err(e) = output::write_split_positions(&split_positions, output_positions) {
-        eprintln!("Error writing split positions file: {}", e);
-        process::exit(1);
+    exit_on_error(stats_writer.finish(), "Error writing stats file");
+    exit_on_error(positions_writer.finish(), "Error writing split positions file");
+    exit_on_error(seqs_writer.finish(), "Error writing new sequences file");
+    if let Some(writer) = agp_writer {
+        exit_on_error(writer.finish(), "Error writing AGP file");
     }
 
-    // 输出新的FASTA序列
-    if let Err(e) = output::write_new_sequences(&new_sequences, output_seqs) {
-        eprintln!("Error writing new sequences file: {}", e);
-        process::exit(1);
+    // 输出组装统计汇总
+    if let Some(path) = &outputs.summary {
+        let mut writer = exit_on_error(output::create_output(path, Compression::None, false), "Error writing summary file");
+        exit_on_error(output::write_assembly_summary(&mut writer, &scaffold_stats.summary(), &contig_stats.summary()), "Error writing summary file");
+        exit_on_error(writer.finish(), "Error writing summary file");
     }
 
-    println!("Processing based on N positions completed successfully.");
+    println!("Processing based on {} breaks completed successfully.", options.break_rule.name());
 }
 
-fn run_split_bed(input_fasta: &str, bed_file: &str, output_seqs: &str) {
-    use crate::bed::read_bed;
-    use crate::split_bed::{split_by_bed};
-    use crate::output::write_new_sequences_bed;
+/// 按区间记录从FASTA文件中提取序列
+/// 有.fai索引时直接按区间随机读取，否则流式读取FASTA文件，内存中最多保留一条序列
+fn extract_regions(input_fasta: &str, records: &[bed::BedRecord], cds_only: bool, group_by_chrom: bool) -> (Vec<split_bed::BedSplitResult>, Vec<String>) {
+    use crate::split_bed::{split_by_bed, split_by_bed_streaming};
 
-    // 读取FASTA文件
-    let sequences = match fasta::read_fasta(input_fasta) {
-        Ok(seq) => seq,
-        Err(e) => {
-            eprintln!("Error reading FASTA file: {}", e);
-            process::exit(1);
+    let indexed = exit_on_error(faidx::IndexedFasta::open(input_fasta), "Error reading FASTA index");
+    match indexed {
+        Some(mut indexed) => exit_on_error(split_by_bed(&mut indexed, records, cds_only, group_by_chrom), "Error reading FASTA file"),
+        None => {
+            // 没有索引时逐条读取序列，每条序列只访问一次，结果仍按区间记录的顺序输出
+            let reader = exit_on_error(fasta::open_fasta(input_fasta), "Error reading FASTA file");
+            exit_on_error(split_by_bed_streaming(reader, records, cds_only), "Error reading FASTA file")
         }
-    };
+    }
+}
 
+/// 输出切割错误信息和切割后的序列
+fn write_split_results(split_results: &[split_bed::BedSplitResult], errors: &[String], outputs: &RegionOutputs) {
+    // 输出错误信息
+    for error in errors {
+        eprintln!("{}", error);
+    }
+
+    // 输出新的FASTA序列
+    let mut writer = exit_on_error(output::create_output(&outputs.seqs, outputs.compression, outputs.write_gzi), "Error writing new sequences file");
+    exit_on_error(output::write_new_sequences_bed(&mut writer, split_results), "Error writing new sequences file");
+    exit_on_error(writer.finish(), "Error writing new sequences file");
+}
+
+fn run_split_bed(input_fasta: &str, bed_file: &str, cds_only: bool, outputs: &RegionOutputs) {
     // 读取BED文件
-    let bed_records = match read_bed(bed_file) {
-        Ok(records) => records,
-        Err(e) => {
-            eprintln!("Error reading BED file: {}", e);
+    let bed_records = exit_on_error(bed::read_bed(bed_file), "Error reading BED file");
+
+    // 根据BED文件切割序列
+    let (split_results, errors) = extract_regions(input_fasta, &bed_records, cds_only, outputs.group_by_chrom);
+
+    // 输出错误信息和新的FASTA序列
+    write_split_results(&split_results, &errors, outputs);
+
+    println!("Processing based on BED file completed successfully.");
+}
+
+fn run_split_gff(input_fasta: &str, gff_file: &str, types: &[String], part_type: &str, outputs: &RegionOutputs) {
+    // 读取GFF3/GTF文件，按类型选出特征并转换成区间记录
+    let features = exit_on_error(gff::read_gff(gff_file), "Error reading GFF file");
+    let (records, mut errors) = split_gff::select_features(&features, types, part_type);
+
+    // 根据特征区间切割序列
+    let (split_results, split_errors) = extract_regions(input_fasta, &records, false, outputs.group_by_chrom);
+    errors.extend(split_errors);
+
+    // 输出错误信息和新的FASTA序列
+    write_split_results(&split_results, &errors, outputs);
+
+    println!("Processing based on GFF file completed successfully.");
+}
+
+fn run_split_window(input_fasta: &str, output_seqs: &str, output_bed: &str, options: &split_window::WindowOptions, compression: Compression, write_gzi: bool) {
+    // 打开FASTA文件，逐条读取序列
+    let reader = exit_on_error(fasta::open_fasta(input_fasta), "Error reading FASTA file");
+
+    // 创建输出文件
+    let mut seqs_writer = exit_on_error(output::create_output(output_seqs, compression, write_gzi), "Error writing new sequences file");
+    let mut bed_writer = exit_on_error(output::create_output(output_bed, Compression::None, false), "Error writing BED file");
+
+    for record in reader {
+        let sequence = exit_on_error(record, "Error reading FASTA file");
+
+        // 按窗口切割序列，输出窗口坐标和窗口序列
+        let (new_sequences, split_positions) = split_window::split_windows(&sequence, options);
+        exit_on_error(output::write_split_positions_bed(&mut bed_writer, &split_positions), "Error writing BED file");
+        exit_on_error(output::write_new_sequences(&mut seqs_writer, &new_sequences), "Error writing new sequences file");
+    }
+
+    exit_on_error(seqs_writer.finish(), "Error writing new sequences file");
+    exit_on_error(bed_writer.finish(), "Error writing BED file");
+
+    println!("Processing based on fixed windows completed successfully.");
+}
+
+fn run_split_parts(
+    input_fasta: &str,
+    output_dir: &str,
+    mode: &split_parts::PartMode,
+    template: Option<&str>,
+    manifest: &str,
+    compression: Option<Compression>,
+    write_gzi: bool,
+) {
+    // 第一遍只读取序列标识符和长度，确定每条序列所属的文件
+    // 均衡拆分需要全部序列长度；文件名含{id}时还需要每个文件第一条序列的标识符
+    // 未指定模板时，根据第一条序列是否带碱基质量选择.fa或.fq后缀
+    let stem = split_parts::input_stem(input_fasta);
+    let keep_ids = template.is_some_and(|template| template.contains("{id}"));
+    let mut fastq = None;
+    let mut limit_splitter = mode.limit_splitter();
+    let mut lengths: Vec<u64> = Vec::new();
+    let mut ids: Vec<String> = Vec::new();
+    let mut parts: Vec<split_parts::PartInfo> = Vec::new();
+    let mut first_ids: Vec<String> = Vec::new();
+    let reader = exit_on_error(fasta::open_fasta(input_fasta), "Error reading FASTA file");
+    for record in reader {
+        let sequence = exit_on_error(record, "Error reading FASTA file");
+        let length = sequence.sequence.len() as u64;
+        fastq.get_or_insert(sequence.quality.is_some());
+        match limit_splitter.as_mut() {
+            Some(splitter) => add_to_part(&mut parts, &mut first_ids, splitter.next_part(length), &sequence.id, length),
+            None => {
+                lengths.push(length);
+                if keep_ids {
+                    ids.push(sequence.id);
+                }
+            }
+        }
+    }
+    let assignments = match mode {
+        split_parts::PartMode::Balanced(count) => split_parts::assign_balanced(&lengths, *count),
+        _ => Vec::new(),
+    };
+    for (i, &part) in assignments.iter().enumerate() {
+        add_to_part(&mut parts, &mut first_ids, part, ids.get(i).map_or("", String::as_str), lengths[i]);
+    }
+
+    let template = template.map(str::to_string)
+        .unwrap_or_else(|| split_parts::default_template(mode, fastq.unwrap_or(false)));
+
+    // 写入任何文件之前，检查文件名是否重复以及--gzi是否可用
+    let mut file_names = HashSet::new();
+    let mut compressions = Vec::with_capacity(parts.len());
+    for (index, (part, first_id)) in parts.iter_mut().zip(&first_ids).enumerate() {
+        let file_name = split_parts::part_file_name(&template, &stem, index + 1, first_id);
+        if !file_names.insert(file_name.clone()) {
+            eprintln!("File name {} is produced more than once; use {{index}} or {{id}} in --template", file_name);
             process::exit(1);
         }
+        part.file = Path::new(output_dir).join(&file_name).to_string_lossy().into_owned();
+        let part_compression = compression.unwrap_or_else(|| Compression::from_path(&part.file));
+        check_gzi(&part.file, part_compression, write_gzi);
+        compressions.push(part_compression);
+    }
+    exit_on_error(std::fs::create_dir_all(output_dir), "Error creating output directory");
+
+    // 第二遍写出序列；每个文件写完最后一条序列后立即关闭
+    // 顺序拆分同时只有一个文件打开；均衡拆分每批最多打开MAX_OPEN_PARTS个文件
+    let batch_size = match mode {
+        split_parts::PartMode::Balanced(_) => split_parts::MAX_OPEN_PARTS,
+        _ => parts.len().max(1),
     };
+    for batch_start in (0..parts.len()).step_by(batch_size) {
+        let batch = batch_start..(batch_start + batch_size).min(parts.len());
+        let mut limit_splitter = mode.limit_splitter();
+        let mut remaining: Vec<usize> = parts.iter().map(|part| part.records).collect();
+        let mut writers: Vec<Option<compress::OutputWriter>> = (0..parts.len()).map(|_| None).collect();
 
-    // 根据BED文件切割序列
-    let (split_results, errors) = split_by_bed(&sequences, &bed_records);
+        let reader = exit_on_error(fasta::open_fasta(input_fasta), "Error reading FASTA file");
+        for (i, record) in reader.enumerate() {
+            let sequence = exit_on_error(record, "Error reading FASTA file");
+            let part = match limit_splitter.as_mut() {
+                Some(splitter) => splitter.next_part(sequence.sequence.len() as u64),
+                None => assignments[i],
+            };
+            if !batch.contains(&part) {
+                continue;
+            }
 
-    // 输出错误信息
-    for error in errors {
-        eprintln!("{}", error);
+            let writer = writers[part].get_or_insert_with(|| {
+                exit_on_error(output::create_output(&parts[part].file, compressions[part], write_gzi), "Error writing part file")
+            });
+            exit_on_error(output::write_new_sequences(writer, std::slice::from_ref(&sequence)), "Error writing part file");
+            remaining[part] -= 1;
+            if remaining[part] == 0 {
+                let writer = writers[part].take().expect("opened above");
+                exit_on_error(writer.finish(), "Error writing part file");
+            }
+        }
     }
 
-    // 输出新的FASTA序列
-    if let Err(e) = write_new_sequences_bed(&split_results, output_seqs) {
-        eprintln!("Error writing new sequences file: {}", e);
-        process::exit(1);
+    // 输出拆分文件清单
+    let mut manifest_writer = exit_on_error(output::create_output(manifest, Compression::None, false), "Error writing manifest file");
+    exit_on_error(output::write_parts_manifest(&mut manifest_writer, &parts), "Error writing manifest file");
+    exit_on_error(manifest_writer.finish(), "Error writing manifest file");
+
+    println!("Split into {} part files; manifest written to {}.", parts.len(), manifest);
+}
+
+/// 把一条序列计入拆分计划中的第part个文件，记录每个文件第一条序列的标识符
+fn add_to_part(parts: &mut Vec<split_parts::PartInfo>, first_ids: &mut Vec<String>, part: usize, id: &str, length: u64) {
+    if part >= parts.len() {
+        parts.resize_with(part + 1, || split_parts::PartInfo { file: String::new(), records: 0, bases: 0 });
+        first_ids.resize(part + 1, String::new());
     }
+    if parts[part].records == 0 {
+        first_ids[part] = id.to_string();
+    }
+    parts[part].records += 1;
+    parts[part].bases += length;
+}
 
-    println!("Processing based on BED file completed successfully.");
+fn run_join_agp(input_fasta: &str, agp_file: &str, output_seqs: &str, compression: Compression, write_gzi: bool) {
+    // 读取contig序列和AGP文件
+    let contigs = exit_on_error(fasta::read_fasta(input_fasta), "Error reading FASTA file");
+    let records = exit_on_error(agp::read_agp(agp_file), "Error reading AGP file");
+
+    // 根据AGP文件拼接scaffold
+    let (scaffolds, unused) = exit_on_error(join_agp::join_by_agp(&contigs, &records), "Error joining contigs");
+
+    // 输出AGP中未使用的contig
+    for id in unused {
+        eprintln!("Contig {} not used in AGP file", id);
+    }
+
+    // 输出拼接后的序列
+    let mut writer = exit_on_error(output::create_output(output_seqs, compression, write_gzi), "Error writing new sequences file");
+    exit_on_error(output::write_new_sequences(&mut writer, &scaffolds), "Error writing new sequences file");
+    exit_on_error(writer.finish(), "Error writing new sequences file");
+
+    println!("Joining contigs based on AGP file completed successfully.");
+}
+
+fn run_faidx(input_fasta: &str, output: &str) {
+    // 扫描FASTA文件生成索引
+    let records = exit_on_error(faidx::build_index(input_fasta), "Error indexing FASTA file");
+
+    // 输出.fai文件
+    exit_on_error(faidx::write_fai(&records, output), "Error writing FAI file");
+
+    println!("FASTA index written to {}.", output);
 }
@@ -1,46 +1,137 @@
-use crate::stats::SequenceStats;
-use crate::split::SplitPosition;
+use crate::stats::{AssemblySummary, SequenceStats};
+use crate::split::{FragmentStatus, SplitPosition};
 use crate::fasta::FastaSequence;
 use crate::split_bed::BedSplitResult;
-use std::fs::File;
+use crate::split_parts::PartInfo;
+use crate::compress::{Compression, OutputWriter};
 use std::io::{self, Write};
 
-/// 将序列统计结果写入指定文件
-pub fn write_stats(stats: &Vec<SequenceStats>, file_path: &str) -> io::Result<()> {
-    let mut file = File::create(file_path)?;
-    writeln!(file, "id\ttotal_length\tn_count")?;
-    for stat in stats {
-        writeln!(file, "{}\t{}\t{}", stat.id, stat.total_length, stat.n_count)?;
-    }
+/// 创建输出文件，按指定方式压缩
+/// 使用BGZF压缩且write_index为true时，同时生成.gzi索引
+pub fn create_output(file_path: &str, compression: Compression, write_index: bool) -> io::Result<OutputWriter> {
+    OutputWriter::create(file_path, compression, write_index)
+}
+
+/// 写入序列统计结果的表头
+pub fn write_stats_header<W: Write>(writer: &mut W) -> io::Result<()> {
+    writeln!(writer, "id\ttotal_length\tn_count\tbreak_rule\tbreak_count\tbreak_length")
+}
+
+/// 将单条序列的统计结果写入输出
+pub fn write_stats<W: Write>(writer: &mut W, stat: &SequenceStats) -> io::Result<()> {
+    writeln!(writer, "{}\t{}\t{}\t{}\t{}\t{}", stat.id, stat.total_length, stat.n_count,
+        stat.break_rule, stat.break_count, stat.break_length)
+}
+
+/// 将scaffold和contig两个层面的组装统计汇总写入输出
+pub fn write_assembly_summary<W: Write>(writer: &mut W, scaffold: &AssemblySummary, contig: &AssemblySummary) -> io::Result<()> {
+    writeln!(writer, "metric\tscaffold\tcontig")?;
+    writeln!(writer, "sequence_count\t{}\t{}", scaffold.sequence_count, contig.sequence_count)?;
+    writeln!(writer, "total_length\t{}\t{}", scaffold.total_length, contig.total_length)?;
+    writeln!(writer, "N50\t{}\t{}", scaffold.n50, contig.n50)?;
+    writeln!(writer, "L50\t{}\t{}", scaffold.l50, contig.l50)?;
+    writeln!(writer, "N90\t{}\t{}", scaffold.n90, contig.n90)?;
+    writeln!(writer, "L90\t{}\t{}", scaffold.l90, contig.l90)?;
+    writeln!(writer, "auN\t{:.2}\t{:.2}", scaffold.au_n, contig.au_n)?;
+    writeln!(writer, "longest\t{}\t{}", scaffold.longest, contig.longest)?;
+    writeln!(writer, "shortest\t{}\t{}", scaffold.shortest, contig.shortest)?;
+    writeln!(writer, "GC_percent\t{:.2}\t{:.2}", scaffold.gc_percent, contig.gc_percent)?;
+    writeln!(writer, "gap_count\t{}\t{}", scaffold.gap_count, contig.gap_count)?;
+    writeln!(writer, "gap_length\t{}\t{}", scaffold.gap_length, contig.gap_length)?;
     Ok(())
 }
 
-/// 将切割位置记录写入指定文件
-pub fn write_split_positions(positions: &Vec<SplitPosition>, file_path: &str) -> io::Result<()> {
-    let mut file = File::create(file_path)?;
-    writeln!(file, "id\tstart\tend\tnew_id")?;
+/// 写入切割位置记录的表头
+pub fn write_split_positions_header<W: Write>(writer: &mut W) -> io::Result<()> {
+    writeln!(writer, "id\tstart\tend\tnew_id\tlength\tgap_after\tstatus\tcut_by")
+}
+
+/// 将切割位置记录写入输出
+pub fn write_split_positions<W: Write>(writer: &mut W, positions: &[SplitPosition]) -> io::Result<()> {
     for position in positions {
-        writeln!(file, "{}\t{}\t{}\t{}", position.id, position.start, position.end, position.new_id)?;
+        writeln!(writer, "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}", position.id, position.start, position.end, position.new_id,
+            position.end + 1 - position.start, position.gap_after, position.status.as_str(), position.cut_by)?;
+    }
+    Ok(())
+}
+
+/// 将切割位置记录按BED格式（0-based，左闭右开）写入输出，第4列为新序列标识符
+/// 被跳过的区间没有对应的输出序列，不写入BED
+pub fn write_split_positions_bed<W: Write>(writer: &mut W, positions: &[SplitPosition]) -> io::Result<()> {
+    for position in positions.iter().filter(|position| position.status != FragmentStatus::Dropped) {
+        writeln!(writer, "{}\t{}\t{}\t{}", position.id, position.start - 1, position.end, position.new_id)?;
+    }
+    Ok(())
+}
+
+/// 写入拆分文件清单：每个文件的路径、序列条数和碱基总数
+pub fn write_parts_manifest<W: Write>(writer: &mut W, parts: &[PartInfo]) -> io::Result<()> {
+    writeln!(writer, "file\trecords\tbases")?;
+    for part in parts {
+        writeln!(writer, "{}\t{}\t{}", part.file, part.records, part.bases)?;
     }
     Ok(())
 }
 
-/// 将新的FASTA序列写入指定文件
-pub fn write_new_sequences(sequences: &Vec<FastaSequence>, file_path: &str) -> io::Result<()> {
-    let mut file = File::create(file_path)?;
+/// 将新的序列写入输出；带碱基质量的序列按FASTQ格式输出
+pub fn write_new_sequences<W: Write>(writer: &mut W, sequences: &[FastaSequence]) -> io::Result<()> {
     for seq in sequences {
-        writeln!(file, ">{} {}", seq.id, seq.description)?;
-        writeln!(file, "{}", seq.sequence)?;
+        match &seq.quality {
+            Some(quality) => {
+                writeln!(writer, "@{} {}", seq.id, seq.description)?;
+                writeln!(writer, "{}", seq.sequence)?;
+                writeln!(writer, "+")?;
+                writeln!(writer, "{}", quality)?;
+            }
+            None => {
+                writeln!(writer, ">{} {}", seq.id, seq.description)?;
+                writeln!(writer, "{}", seq.sequence)?;
+            }
+        }
     }
     Ok(())
 }
 
-/// 将基于BED文件切割的FASTA序列写入指定文件
-pub fn write_new_sequences_bed(results: &Vec<BedSplitResult>, file_path: &str) -> io::Result<()> {
-    let mut file = File::create(file_path)?;
+/// 将基于BED文件切割的序列写入输出；带碱基质量的结果按FASTQ格式输出
+pub fn write_new_sequences_bed<W: Write>(writer: &mut W, results: &[BedSplitResult]) -> io::Result<()> {
     for result in results {
-        writeln!(file, ">{}", result.new_id)?;
-        writeln!(file, "{}", result.sequence)?;
+        let header = match &result.description {
+            Some(description) => format!("{} {}", result.new_id, description),
+            None => result.new_id.clone(),
+        };
+        match &result.quality {
+            Some(quality) => {
+                writeln!(writer, "@{}", header)?;
+                writeln!(writer, "{}", result.sequence)?;
+                writeln!(writer, "+")?;
+                writeln!(writer, "{}", quality)?;
+            }
+            None => {
+                writeln!(writer, ">{}", header)?;
+                writeln!(writer, "{}", result.sequence)?;
+            }
+        }
     }
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::split_window::{split_windows, WindowOptions};
+
+    #[test]
+    fn test_bed_leaves_out_skipped_windows() {
+        let sequence = FastaSequence {
+            id: "chr1".to_string(),
+            description: String::new(),
+            sequence: "ACGTNNNNACNN".to_string(),
+            quality: None,
+        };
+        let options = WindowOptions { window: 4, step: 4, max_n_fraction: Some(0.5) };
+        let (_, positions) = split_windows(&sequence, &options);
+        let mut bed = Vec::new();
+        write_split_positions_bed(&mut bed, &positions).unwrap();
+        assert_eq!(String::from_utf8(bed).unwrap(), "chr1\t0\t4\tchr1_1\nchr1\t8\t12\tchr1_3\n");
+    }
+}
@@ -1,117 +1,317 @@
 use crate::fasta::FastaSequence;
+use regex::Regex;
+
+/// 切割片段的处理结果
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum FragmentStatus {
+    Kept,    // 正常输出
+    Short,   // 短于最小长度，但仍然输出
+    Dropped, // 短于最小长度，已丢弃
+}
+
+impl FragmentStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FragmentStatus::Kept => "kept",
+            FragmentStatus::Short => "short",
+            FragmentStatus::Dropped => "dropped",
+        }
+    }
+}
 
 /// 代表切割位置的结构体
 pub struct SplitPosition {
     pub id: String,       // 原序列标识符
     pub start: usize,     // 切割起始位置
     pub end: usize,       // 切割终止位置
-    pub new_id: String,   // 新序列标识符
+    pub new_id: String,   // 新序列标识符，丢弃的片段为"."
+    pub gap_after: usize, // 片段之后切割区间的长度，序列末尾没有切割区间时为0
+    pub status: FragmentStatus, // 片段的处理结果
+    pub cut_by: &'static str,   // 片段之后的切割由哪条规则产生，没有切割时为"."
 }
 
-/// 根据N的位置切割序列，并生成新的序列和切割位置记录
-/// 返回一个元组，包含新的序列列表和切割位置列表
-pub fn split_sequences(sequences: &Vec<FastaSequence>) -> (Vec<FastaSequence>, Vec<SplitPosition>) {
-    let mut new_sequences = Vec::new();
-    let mut split_positions = Vec::new();
+/// 切割点的判定规则
+pub enum BreakRule {
+    N,            // 连续的N/n
+    Lowercase,    // 连续的小写字母（soft-masked重复序列）
+    Iupac,        // 连续的IUPAC简并碱基（含N）
+    Regex(Regex), // 正则表达式匹配到的区间
+}
+
+impl BreakRule {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BreakRule::N => "N",
+            BreakRule::Lowercase => "lowercase",
+            BreakRule::Iupac => "iupac",
+            BreakRule::Regex(_) => "regex",
+        }
+    }
+
+    /// 单个碱基是否属于切割区间，正则规则不按单个碱基判断
+    fn is_break(&self, base: u8) -> bool {
+        match self {
+            BreakRule::N => base == b'N' || base == b'n',
+            BreakRule::Lowercase => base.is_ascii_lowercase(),
+            BreakRule::Iupac => matches!(base.to_ascii_uppercase(),
+                b'R' | b'Y' | b'S' | b'W' | b'K' | b'M' | b'B' | b'D' | b'H' | b'V' | b'N'),
+            BreakRule::Regex(_) => false,
+        }
+    }
+}
+
+/// 切割参数
+pub struct SplitOptions {
+    pub break_rule: BreakRule, // 切割点的判定规则
+    pub min_gap: usize,    // 切割区间达到该长度才作为切割点
+    pub min_length: usize, // 短于该长度的片段视为过短
+    pub keep_short: bool,  // 过短片段是否仍然输出（否则丢弃）
+}
+
+impl BreakRule {
+    /// 找出序列中所有满足切割规则的区间，返回[start, end)坐标列表（不考虑min_gap）
+    pub fn find_runs(&self, sequence: &str) -> Vec<(usize, usize)> {
+        match self {
+            BreakRule::Regex(regex) => regex.find_iter(sequence)
+                .filter(|m| !m.is_empty())
+                .map(|m| (m.start(), m.end()))
+                .collect(),
+            _ => find_runs(sequence.as_bytes(), self),
+        }
+    }
+}
 
-    for seq in sequences {
-        let mut start = 0;
-        let mut segment_count = 1;
-
-        for (i, c) in seq.sequence.chars().enumerate() {
-            if c == 'N' || c == 'n' {
-                if start < i {
-                    let new_id = format!("{}_{}", seq.id, segment_count);
-                    let new_sequence = FastaSequence {
-                        id: new_id.clone(),
-                        description: seq.description.clone(),
-                        sequence: seq.sequence[start..i].to_string(),
-                    };
-                    new_sequences.push(new_sequence);
-
-                    let split_position = SplitPosition {
-                        id: seq.id.clone(),
-                        start: start + 1,
-                        end: i,
-                        new_id: new_id.clone(),
-                    };
-                    split_positions.push(split_position);
-
-                    segment_count += 1;
-                }
-                start = i + 1;
+/// 找出作为切割点的区间，返回[start, end)坐标列表
+/// 长度不足min_gap的区间保留在片段内部，但位于序列两端的区间总是被切除
+fn find_gaps(sequence: &str, rule: &BreakRule, min_gap: usize) -> Vec<(usize, usize)> {
+    rule.find_runs(sequence).into_iter()
+        .filter(|&(run_start, run_end)| run_end - run_start >= min_gap || run_start == 0 || run_end == sequence.len())
+        .collect()
+}
+
+/// 找出连续满足切割规则的碱基区间
+fn find_runs(sequence: &[u8], rule: &BreakRule) -> Vec<(usize, usize)> {
+    let mut runs = Vec::new();
+    let mut i = 0;
+    while i < sequence.len() {
+        if rule.is_break(sequence[i]) {
+            let run_start = i;
+            while i < sequence.len() && rule.is_break(sequence[i]) {
+                i += 1;
             }
+            runs.push((run_start, i));
+        } else {
+            i += 1;
         }
-        if start < seq.sequence.len() {
-            let new_id = format!("{}_{}", seq.id, segment_count);
-            let new_sequence = FastaSequence {
-                id: new_id.clone(),
-                description: seq.description.clone(),
-                sequence: seq.sequence[start..].to_string(),
+    }
+    runs
+}
+
+/// 统计一条序列被切除的区间数和总长度（包括两端的区间）
+pub fn count_breaks(length: usize, positions: &[SplitPosition]) -> (usize, usize) {
+    let Some(first) = positions.first() else {
+        // 整条序列都属于切割区间
+        return if length > 0 { (1, length) } else { (0, 0) };
+    };
+    let leading = first.start - 1;
+    let mut count = if leading > 0 { 1 } else { 0 };
+    let mut total = leading;
+    for position in positions {
+        if position.gap_after > 0 {
+            count += 1;
+            total += position.gap_after;
+        }
+    }
+    (count, total)
+}
+
+/// 根据N的位置切割单条序列，并生成新的序列和切割位置记录
+/// FASTQ输入的碱基质量随序列一起切割
+/// 返回一个元组，包含该序列切割出的新序列列表和切割位置列表（含丢弃的片段）
+pub fn split_sequence(seq: &FastaSequence, options: &SplitOptions) -> (Vec<FastaSequence>, Vec<SplitPosition>) {
+    let mut new_sequences = Vec::new();
+    let mut split_positions = Vec::new();
+
+    let length = seq.sequence.len();
+    let mut gaps = find_gaps(&seq.sequence, &options.break_rule, options.min_gap);
+    gaps.push((length, length));
+
+    let mut start = 0;
+    let mut segment_count = 1;
+
+    for (gap_start, gap_end) in gaps {
+        if start < gap_start {
+            let status = if gap_start - start >= options.min_length {
+                FragmentStatus::Kept
+            } else if options.keep_short {
+                FragmentStatus::Short
+            } else {
+                FragmentStatus::Dropped
             };
-            new_sequences.push(new_sequence);
 
-            let split_position = SplitPosition {
+            let new_id = if status == FragmentStatus::Dropped {
+                ".".to_string()
+            } else {
+                let new_id = format!("{}_{}", seq.id, segment_count);
+                new_sequences.push(FastaSequence {
+                    id: new_id.clone(),
+                    description: seq.description.clone(),
+                    sequence: seq.sequence[start..gap_start].to_string(),
+                    quality: seq.quality.as_ref().map(|q| q[start..gap_start].to_string()),
+                });
+                segment_count += 1;
+                new_id
+            };
+
+            split_positions.push(SplitPosition {
                 id: seq.id.clone(),
                 start: start + 1,
-                end: seq.sequence.len(),
-                new_id: new_id.clone(),
-            };
-            split_positions.push(split_position);
+                end: gap_start,
+                new_id,
+                gap_after: gap_end - gap_start,
+                status,
+                cut_by: if gap_end > gap_start { options.break_rule.name() } else { "." },
+            });
         }
+        start = gap_end;
     }
 
     (new_sequences, split_positions)
 }
 
-//#[cfg(test)]
-//mod tests {
-//    use super::*;
-//    use crate::fasta::FastaSequence;
-
-//    #[test]
-//    fn test_split_sequences() {
-//        let sequences = vec![
-//            FastaSequence {
-//                id: "seq1".to_string(),
-//                description: "description1".to_string(),
-//                sequence: "ATCGNNNNATCG".to_string(),
-//            },
-//            FastaSequence {
-//                id: "seq2".to_string(),
-//                description: "description2".to_string(),
-//                sequence: "GCTAGCTA".to_string(),
-//            }
-//        ];
-
-//        let (new_sequences, split_positions) = split_sequences(&sequences);
-        
-//        // 检查新序列的数量
-//        assert_eq!(new_sequences.len(), 3);
-
-//        // 验证第一条序列的切割结果
-//        assert_eq!(new_sequences[0].id, "seq1_1");
-//        assert_eq!(new_sequences[0].sequence, "ATCG");
-
-//        assert_eq!(new_sequences[1].id, "seq1_2");
-//        assert_eq!(new_sequences[1].sequence, "ATCG");
-
-//        // 验证第二条序列（没有N）的切割结果
-//        assert_eq!(new_sequences[2].id, "seq2_1");
-//        assert_eq!(new_sequences[2].sequence, "GCTA");
-
-//        // 检查切割位置记录的数量
-//        assert_eq!(split_positions.len(), 2);
-
-//        // 验证第一条序列的切割位置
-//        assert_eq!(split_positions[0].id, "seq1");
-//        assert_eq!(split_positions[0].start, 1);
-//        assert_eq!(split_positions[0].end, 4);
-//        assert_eq!(split_positions[0].new_id, "seq1_1");
-
-//        assert_eq!(split_positions[1].id, "seq1");
-//        assert_eq!(split_positions[1].start, 9);
-//        assert_eq!(split_positions[1].end, 12);
-//        assert_eq!(split_positions[1].new_id, "seq1_2");
-//    }
-//}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fasta::FastaSequence;
+
+    fn sequence(id: &str, bases: &str) -> FastaSequence {
+        FastaSequence {
+            id: id.to_string(),
+            description: format!("{} description", id),
+            sequence: bases.to_string(),
+            quality: None,
+        }
+    }
+
+    fn options(min_gap: usize, min_length: usize, keep_short: bool) -> SplitOptions {
+        SplitOptions { break_rule: BreakRule::N, min_gap, min_length, keep_short }
+    }
+
+    #[test]
+    fn test_split_sequence() {
+        let options = options(1, 0, false);
+        let (new_sequences, split_positions) = split_sequence(&sequence("seq1", "ATCGNNNNATCG"), &options);
+
+        // 验证第一条序列的切割结果
+        assert_eq!(new_sequences.len(), 2);
+        assert_eq!(new_sequences[0].id, "seq1_1");
+        assert_eq!(new_sequences[0].sequence, "ATCG");
+        assert_eq!(new_sequences[0].description, "seq1 description");
+        assert_eq!(new_sequences[1].id, "seq1_2");
+        assert_eq!(new_sequences[1].sequence, "ATCG");
+
+        // 验证第一条序列的切割位置
+        assert_eq!(split_positions.len(), 2);
+        assert_eq!((split_positions[0].start, split_positions[0].end), (1, 4));
+        assert_eq!(split_positions[0].new_id, "seq1_1");
+        assert_eq!(split_positions[0].gap_after, 4);
+        assert_eq!(split_positions[0].cut_by, "N");
+        assert_eq!((split_positions[1].start, split_positions[1].end), (9, 12));
+        assert_eq!(split_positions[1].new_id, "seq1_2");
+        assert_eq!(split_positions[1].gap_after, 0);
+        assert_eq!(split_positions[1].cut_by, ".");
+
+        // 验证第二条序列（没有N）的切割结果
+        let (new_sequences, split_positions) = split_sequence(&sequence("seq2", "GCTAGCTA"), &options);
+        assert_eq!(new_sequences.len(), 1);
+        assert_eq!(new_sequences[0].id, "seq2_1");
+        assert_eq!(new_sequences[0].sequence, "GCTAGCTA");
+        assert_eq!((split_positions[0].start, split_positions[0].end), (1, 8));
+    }
+
+    #[test]
+    fn test_min_gap_keeps_short_runs_inside_fragments() {
+        // 长度不足min_gap的N区间保留在片段内，两端的N总是被切除
+        let seq = sequence("seq1", "NNACGNNTTNNNNNGGN");
+        let (new_sequences, split_positions) = split_sequence(&seq, &options(3, 0, false));
+        let bases: Vec<&str> = new_sequences.iter().map(|s| s.sequence.as_str()).collect();
+        assert_eq!(bases, vec!["ACGNNTT", "GG"]);
+        assert_eq!((split_positions[0].start, split_positions[0].end), (3, 9));
+        assert_eq!(split_positions[0].gap_after, 5);
+        assert_eq!(split_positions[1].gap_after, 1);
+        assert_eq!(count_breaks(seq.sequence.len(), &split_positions), (3, 8));
+    }
+
+    #[test]
+    fn test_min_length_drops_or_keeps_short_fragments() {
+        let seq = sequence("seq1", "ACNNNACGTACGNNAAA");
+
+        // 过短片段被丢弃，但仍然记录位置，编号只分配给输出的片段
+        let (new_sequences, split_positions) = split_sequence(&seq, &options(1, 3, false));
+        assert_eq!(new_sequences.len(), 2);
+        assert_eq!(new_sequences[0].id, "seq1_1");
+        assert_eq!(new_sequences[0].sequence, "ACGTACG");
+        assert_eq!(new_sequences[1].sequence, "AAA");
+        let statuses: Vec<FragmentStatus> = split_positions.iter().map(|p| p.status).collect();
+        assert!(statuses == vec![FragmentStatus::Dropped, FragmentStatus::Kept, FragmentStatus::Kept]);
+        assert_eq!(split_positions[0].new_id, ".");
+
+        // --keep-short时过短片段照常输出，只标记为short
+        let (new_sequences, split_positions) = split_sequence(&seq, &options(1, 3, true));
+        assert_eq!(new_sequences.len(), 3);
+        assert_eq!(new_sequences[0].id, "seq1_1");
+        assert_eq!(new_sequences[0].sequence, "AC");
+        assert!(split_positions[0].status == FragmentStatus::Short);
+    }
+
+    #[test]
+    fn test_split_carries_quality() {
+        let mut seq = sequence("read1", "ACGNNTTA");
+        seq.quality = Some("ABCDEFGH".to_string());
+        let (new_sequences, _) = split_sequence(&seq, &options(1, 0, false));
+        assert_eq!(new_sequences[0].quality.as_deref(), Some("ABC"));
+        assert_eq!(new_sequences[1].quality.as_deref(), Some("FGH"));
+    }
+
+    #[test]
+    fn test_all_n_sequence() {
+        let seq = sequence("gap", "NNNN");
+        let (new_sequences, split_positions) = split_sequence(&seq, &options(1, 0, false));
+        assert!(new_sequences.is_empty());
+        assert!(split_positions.is_empty());
+        assert_eq!(count_breaks(4, &split_positions), (1, 4));
+    }
+
+    fn fragments(bases: &str, break_rule: BreakRule, min_gap: usize) -> (Vec<String>, Vec<&'static str>) {
+        let options = SplitOptions { break_rule, min_gap, min_length: 0, keep_short: false };
+        let (new_sequences, split_positions) = split_sequence(&sequence("seq1", bases), &options);
+        (
+            new_sequences.into_iter().map(|s| s.sequence).collect(),
+            split_positions.iter().map(|p| p.cut_by).collect(),
+        )
+    }
+
+    #[test]
+    fn test_break_rules() {
+        let bases = "ACGTacgtACnnGTRYAC";
+        assert_eq!(fragments(bases, BreakRule::N, 1), (vec!["ACGTacgtAC".to_string(), "GTRYAC".to_string()], vec!["N", "."]));
+        assert_eq!(fragments(bases, BreakRule::Lowercase, 1).0, vec!["ACGT", "AC", "GTRYAC"]);
+        assert_eq!(fragments(bases, BreakRule::Lowercase, 3).0, vec!["ACGT", "ACnnGTRYAC"]);
+        let (pieces, cut_by) = fragments(bases, BreakRule::Iupac, 1);
+        assert_eq!(pieces, vec!["ACGTacgtAC", "GT", "AC"]);
+        assert_eq!(cut_by, vec!["iupac", "iupac", "."]);
+
+        let regex = BreakRule::Regex(Regex::new("GT[A-Z]{2}").unwrap());
+        let (pieces, cut_by) = fragments(bases, regex, 1);
+        assert_eq!(pieces, vec!["ACGTacgtACnn", "AC"]);
+        assert_eq!(cut_by, vec!["regex", "."]);
+    }
+
+    #[test]
+    fn test_find_runs() {
+        assert_eq!(BreakRule::N.find_runs("NNACNnNA"), vec![(0, 2), (4, 7)]);
+        assert_eq!(BreakRule::Lowercase.find_runs("ACGT"), vec![]);
+        // 正则表达式的空匹配被忽略
+        assert_eq!(BreakRule::Regex(Regex::new("N*").unwrap()).find_runs("ANNA"), vec![(1, 3)]);
+    }
+}
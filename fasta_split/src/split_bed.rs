@@ -1,44 +1,352 @@
 use crate::fasta::FastaSequence;
 use crate::bed::BedRecord;
+use seq_utils::reverse_complement;
+use std::collections::{HashMap, HashSet};
+use std::io;
 
 /// 代表基于BED文件切割的结果的结构体
 pub struct BedSplitResult {
-    pub id: String,         // 原序列标识符
-    pub start: i64,         // 切割起始位置
-    pub end: i64,           // 切割终止位置
     pub new_id: String,     // 新序列标识符
+    pub description: Option<String>, // 新序列的描述信息
     pub sequence: String,   // 新序列
+    pub quality: Option<String>, // 新序列的碱基质量（仅FASTQ）
 }
 
-/// 根据BED文件中的区间切割FASTA文件
-/// 返回一个包含所有切割结果的向量和错误信息
-pub fn split_by_bed(sequences: &Vec<FastaSequence>, bed_records: &Vec<BedRecord>) -> (Vec<BedSplitResult>, Vec<String>) {
+/// 按区间提取序列的数据来源
+/// 可以是内存中的序列，也可以是带.fai索引、支持随机读取的FASTA文件
+pub trait RegionSource {
+    /// 返回指定序列的长度；序列不存在时返回None
+    fn sequence_length(&self, chrom: &str) -> Option<usize>;
+    /// 序列标识符是否重复出现，重复的序列不用于提取
+    fn is_duplicated(&self, _chrom: &str) -> bool {
+        false
+    }
+    /// 提取指定序列[start, end)区间的碱基，以及对应的碱基质量（仅FASTQ）
+    fn fetch(&mut self, chrom: &str, start: usize, end: usize) -> io::Result<(String, Option<String>)>;
+}
+
+/// 内存中序列的标识符索引，按标识符直接查找序列
+/// 同一标识符出现多次时无法确定使用哪一条，记为重复，不提供该标识符的序列
+pub struct SequenceIndex {
+    sequences: HashMap<String, FastaSequence>,
+    duplicated: HashSet<String>,
+}
+
+impl SequenceIndex {
+    pub fn new(sequences: Vec<FastaSequence>) -> Self {
+        let mut index = HashMap::with_capacity(sequences.len());
+        let mut duplicated = HashSet::new();
+        for sequence in sequences {
+            if index.contains_key(&sequence.id) {
+                duplicated.insert(sequence.id.clone());
+            } else {
+                index.insert(sequence.id.clone(), sequence);
+            }
+        }
+        for id in &duplicated {
+            index.remove(id);
+        }
+        SequenceIndex { sequences: index, duplicated }
+    }
+}
+
+impl RegionSource for SequenceIndex {
+    fn sequence_length(&self, chrom: &str) -> Option<usize> {
+        self.sequences.get(chrom).map(|s| s.sequence.len())
+    }
+
+    fn is_duplicated(&self, chrom: &str) -> bool {
+        self.duplicated.contains(chrom)
+    }
+
+    fn fetch(&mut self, chrom: &str, start: usize, end: usize) -> io::Result<(String, Option<String>)> {
+        match self.sequences.get(chrom) {
+            Some(seq) => Ok((
+                seq.sequence[start..end].to_string(),
+                seq.quality.as_ref().map(|q| q[start..end].to_string()),
+            )),
+            None => Err(io::Error::new(io::ErrorKind::NotFound, format!("Sequence {} not found", chrom))),
+        }
+    }
+}
+
+/// 单条BED记录无法提取的原因
+enum SkipReason {
+    NotFound,       // 序列不存在
+    Duplicated,     // 序列标识符重复
+    InvalidRange,   // 区间超出序列范围或为空
+    NoCodingRegion, // cds_only时没有编码区
+}
+
+type Extraction = Result<(String, Option<String>), SkipReason>;
+
+/// 提取单条BED记录对应的序列和碱基质量
+fn extract_record<S: RegionSource>(source: &mut S, record: &BedRecord, cds_only: bool) -> io::Result<Extraction> {
+    if source.is_duplicated(&record.chrom) {
+        return Ok(Err(SkipReason::Duplicated));
+    }
+    let Some(length) = source.sequence_length(&record.chrom) else {
+        return Ok(Err(SkipReason::NotFound));
+    };
+    if record.start < 0 || record.start >= record.end || record.end as usize > length {
+        return Ok(Err(SkipReason::InvalidRange));
+    }
+
+    // 需要提取的区间：外显子块，必要时裁剪到编码区
+    let blocks: Vec<(usize, usize)> = record.blocks.iter()
+        .map(|&(block_start, block_end)| {
+            if cds_only {
+                (block_start.max(record.thick_start), block_end.min(record.thick_end))
+            } else {
+                (block_start, block_end)
+            }
+        })
+        .filter(|&(block_start, block_end)| block_start < block_end)
+        .map(|(block_start, block_end)| (block_start as usize, block_end as usize))
+        .collect();
+    if blocks.is_empty() {
+        return Ok(Err(SkipReason::NoCodingRegion));
+    }
+
+    let mut sequence = String::new();
+    let mut quality: Option<String> = None;
+    for (block_start, block_end) in blocks {
+        let (block_sequence, block_quality) = source.fetch(&record.chrom, block_start, block_end)?;
+        sequence.push_str(&block_sequence);
+        if let Some(block_quality) = block_quality {
+            quality.get_or_insert_with(String::new).push_str(&block_quality);
+        }
+    }
+    if record.reverse {
+        sequence = reverse_complement(&sequence);
+        quality = quality.map(|q| q.chars().rev().collect());
+    }
+    Ok(Ok((sequence, quality)))
+}
+
+/// 按BED记录的原始顺序整理提取结果，生成新序列标识符和错误信息
+fn collect_results(bed_records: &[BedRecord], extractions: Vec<Extraction>) -> (Vec<BedSplitResult>, Vec<String>) {
     let mut results = Vec::new();
     let mut errors = Vec::new();
-    
-    for record in bed_records {
-        if let Some(seq) = sequences.iter().find(|&s| s.id == record.chrom) {
-            let start = record.start as usize;
-            let end = record.end as usize;
-            let new_id = record.name.clone().unwrap_or_else(|| format!("{}_{}", record.chrom, results.len() + 1));
-            
-            if start >= end || end > seq.sequence.len() {
-                errors.push(format!("Invalid BED record for {}: start {}, end {}", record.chrom, record.start, record.end));
-                continue;
-            }
 
-            let sequence = seq.sequence[start..end].to_string();
-            results.push(BedSplitResult {
-                id: record.chrom.clone(),
-                start: record.start,
-                end: record.end,
+    for (record, extraction) in bed_records.iter().zip(extractions) {
+        let new_id = record.name.clone().unwrap_or_else(|| format!("{}_{}", record.chrom, results.len() + 1));
+        match extraction {
+            Ok((sequence, quality)) => results.push(BedSplitResult {
                 new_id,
+                description: record.description.clone(),
                 sequence,
-            });
-        } else {
-            errors.push(format!("Chromosome {} not found in FASTA sequences", record.chrom));
+                quality,
+            }),
+            Err(SkipReason::NotFound) => errors.push(format!("Chromosome {} not found in FASTA sequences", record.chrom)),
+            Err(SkipReason::Duplicated) => errors.push(format!("Chromosome {} appears more than once in FASTA sequences; BED record {} skipped", record.chrom, new_id)),
+            Err(SkipReason::InvalidRange) => errors.push(format!("Invalid BED record for {}: start {}, end {}", record.chrom, record.start, record.end)),
+            Err(SkipReason::NoCodingRegion) => errors.push(format!("No coding region in BED record {} for {}: thickStart {}, thickEnd {}", new_id, record.chrom, record.thick_start, record.thick_end)),
         }
     }
 
     (results, errors)
 }
+
+/// 根据BED文件中的区间切割FASTA文件
+/// BED12记录按外显子块拼接成剪接后的序列；cds_only为true时只保留thickStart到thickEnd之间的部分
+/// '-'链的区间取反向互补序列，碱基质量同时反转
+/// group_by_chrom为true时按染色体集中处理同一序列上的记录，结果仍按BED文件的顺序输出
+/// 返回一个包含所有切割结果的向量和错误信息
+pub fn split_by_bed<S: RegionSource>(source: &mut S, bed_records: &[BedRecord], cds_only: bool, group_by_chrom: bool) -> io::Result<(Vec<BedSplitResult>, Vec<String>)> {
+    let mut order: Vec<usize> = (0..bed_records.len()).collect();
+    if group_by_chrom {
+        // 按染色体第一次出现的顺序分组，组内保持BED顺序
+        let mut first_seen: HashMap<&str, usize> = HashMap::new();
+        for (i, record) in bed_records.iter().enumerate() {
+            first_seen.entry(record.chrom.as_str()).or_insert(i);
+        }
+        order.sort_by_key(|&i| (first_seen[bed_records[i].chrom.as_str()], i));
+    }
+
+    let mut extractions: Vec<Option<Extraction>> = (0..bed_records.len()).map(|_| None).collect();
+    for i in order {
+        extractions[i] = Some(extract_record(source, &bed_records[i], cds_only)?);
+    }
+
+    Ok(collect_results(bed_records, extractions.into_iter().map(|e| e.expect("every record extracted")).collect()))
+}
+
+/// 流式读取FASTA并按染色体分组提取，每条序列只读入并访问一次，内存中最多保留一条序列
+/// 标识符重复的序列上的BED记录全部跳过
+pub fn split_by_bed_streaming<I>(sequences: I, bed_records: &[BedRecord], cds_only: bool) -> io::Result<(Vec<BedSplitResult>, Vec<String>)>
+where
+    I: Iterator<Item = io::Result<FastaSequence>>,
+{
+    let mut groups: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (i, record) in bed_records.iter().enumerate() {
+        groups.entry(record.chrom.as_str()).or_default().push(i);
+    }
+
+    let mut extractions: Vec<Option<Extraction>> = (0..bed_records.len()).map(|_| None).collect();
+    let mut seen = HashSet::new();
+    for sequence in sequences {
+        let sequence = sequence?;
+        let Some(group) = groups.get(sequence.id.as_str()) else {
+            continue;
+        };
+        if !seen.insert(sequence.id.clone()) {
+            // 同一标识符再次出现，之前的提取结果作废
+            for &i in group {
+                extractions[i] = Some(Err(SkipReason::Duplicated));
+            }
+            continue;
+        }
+        let mut source = SequenceIndex::new(vec![sequence]);
+        for &i in group {
+            extractions[i] = Some(extract_record(&mut source, &bed_records[i], cds_only)?);
+        }
+    }
+
+    let extractions = extractions.into_iter()
+        .map(|extraction| extraction.unwrap_or(Err(SkipReason::NotFound)))
+        .collect();
+    Ok(collect_results(bed_records, extractions))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sequence(id: &str, bases: &str, quality: Option<&str>) -> FastaSequence {
+        FastaSequence {
+            id: id.to_string(),
+            description: String::new(),
+            sequence: bases.to_string(),
+            quality: quality.map(|q| q.to_string()),
+        }
+    }
+
+    fn record(chrom: &str, start: i64, end: i64, name: Option<&str>, reverse: bool) -> BedRecord {
+        BedRecord {
+            chrom: chrom.to_string(),
+            start,
+            end,
+            name: name.map(|n| n.to_string()),
+            description: None,
+            reverse,
+            thick_start: start,
+            thick_end: end,
+            blocks: vec![(start, end)],
+        }
+    }
+
+    fn extracted(results: &[BedSplitResult]) -> Vec<(&str, &str)> {
+        results.iter().map(|r| (r.new_id.as_str(), r.sequence.as_str())).collect()
+    }
+
+    #[test]
+    fn test_minus_strand_is_reverse_complemented() {
+        let mut source = SequenceIndex::new(vec![sequence("chr1", "AACCGGTTAC", Some("ABCDEFGHIJ"))]);
+        let records = vec![record("chr1", 0, 4, Some("plus"), false), record("chr1", 2, 7, None, true)];
+        let (results, errors) = split_by_bed(&mut source, &records, false, false).unwrap();
+        assert!(errors.is_empty());
+        assert_eq!(extracted(&results), vec![("plus", "AACC"), ("chr1_2", "ACCGG")]);
+        assert_eq!(results[1].quality.as_deref(), Some("GFEDC"));
+    }
+
+    #[test]
+    fn test_invalid_and_missing_records_are_reported() {
+        let mut source = SequenceIndex::new(vec![sequence("chr1", "ACGTACGT", None)]);
+        let records = vec![
+            record("chr2", 0, 4, None, false),
+            record("chr1", 4, 20, None, false),
+            record("chr1", 3, 3, None, false),
+            record("chr1", 1, 3, None, false),
+        ];
+        let (results, errors) = split_by_bed(&mut source, &records, false, false).unwrap();
+        assert_eq!(extracted(&results), vec![("chr1_1", "CG")]);
+        assert_eq!(errors.len(), 3);
+        assert!(errors[0].contains("not found"));
+    }
+
+    fn spliced_record(reverse: bool) -> BedRecord {
+        // 三个外显子块：[2,5)、[7,10)、[12,16)，编码区[3,14)
+        BedRecord {
+            chrom: "chr1".to_string(),
+            start: 2,
+            end: 16,
+            name: Some("tx1".to_string()),
+            description: None,
+            reverse,
+            thick_start: 3,
+            thick_end: 14,
+            blocks: vec![(2, 5), (7, 10), (12, 16)],
+        }
+    }
+
+    #[test]
+    fn test_bed12_blocks_are_spliced() {
+        let chr1 = "AACGTTTGCANNATGCAA";
+        let mut source = SequenceIndex::new(vec![sequence("chr1", chr1, None)]);
+
+        let (results, _) = split_by_bed(&mut source, &[spliced_record(false)], false, false).unwrap();
+        assert_eq!(results[0].sequence, "CGTGCAATGC");
+
+        // 只保留编码区
+        let (results, _) = split_by_bed(&mut source, &[spliced_record(false)], true, false).unwrap();
+        assert_eq!(results[0].sequence, "GTGCAAT");
+
+        // '-'链先拼接再反向互补
+        let (results, _) = split_by_bed(&mut source, &[spliced_record(true)], true, false).unwrap();
+        assert_eq!(results[0].sequence, "ATTGCAC");
+    }
+
+    #[test]
+    fn test_cds_only_without_coding_region() {
+        let mut source = SequenceIndex::new(vec![sequence("chr1", "AACGTTTGCANNATGCAA", None)]);
+        let mut noncoding = spliced_record(false);
+        noncoding.thick_start = 5;
+        noncoding.thick_end = 5;
+        let (results, errors) = split_by_bed(&mut source, &[noncoding], true, false).unwrap();
+        assert!(results.is_empty());
+        assert!(errors[0].starts_with("No coding region"));
+    }
+
+    #[test]
+    fn test_duplicated_ids_are_skipped() {
+        let sequences = || vec![
+            sequence("chr1", "AAAAAAAA", None),
+            sequence("chr2", "CCCCGGGG", None),
+            sequence("chr1", "TTTTTTTT", None),
+        ];
+        let records = vec![
+            record("chr1", 0, 4, Some("a"), false),
+            record("chr2", 2, 6, Some("b"), false),
+            record("chr1", 4, 8, Some("c"), false),
+        ];
+
+        // 重复的序列不提供任何一条，而不是保留第一条
+        let mut source = SequenceIndex::new(sequences());
+        assert!(source.is_duplicated("chr1"));
+        assert_eq!(source.sequence_length("chr1"), None);
+        let (results, errors) = split_by_bed(&mut source, &records, false, true).unwrap();
+        assert_eq!(extracted(&results), vec![("b", "CCGG")]);
+        assert_eq!(errors.len(), 2);
+        assert!(errors.iter().all(|e| e.contains("appears more than once")));
+
+        // 流式读取时重复在第一条之后才出现，结果与内存索引一致
+        let (streamed, streamed_errors) = split_by_bed_streaming(sequences().into_iter().map(Ok), &records, false).unwrap();
+        assert_eq!(extracted(&streamed), extracted(&results));
+        assert_eq!(streamed_errors, errors);
+    }
+
+    #[test]
+    fn test_streaming_keeps_bed_order() {
+        let sequences = vec![sequence("chr1", "ACGTACGT", None), sequence("chr2", "GGGGCCCC", None)];
+        let records = vec![
+            record("chr2", 0, 2, None, false),
+            record("chr1", 0, 3, None, false),
+            record("chr3", 0, 1, None, false),
+            record("chr2", 4, 8, None, true),
+        ];
+        let (results, errors) = split_by_bed_streaming(sequences.into_iter().map(Ok), &records, false).unwrap();
+        assert_eq!(extracted(&results), vec![("chr2_1", "GG"), ("chr1_2", "ACG"), ("chr2_3", "GGGG")]);
+        assert_eq!(errors, vec!["Chromosome chr3 not found in FASTA sequences".to_string()]);
+    }
+}
@@ -0,0 +1,248 @@
+use crate::bed::BedRecord;
+use seq_utils::gff::{is_transcript_type, GffFeature};
+use std::collections::{HashMap, HashSet};
+
+/// 把一组特征片段转换成一条带外显子块的区间记录
+/// head为提供ID/Name/Parent的特征；CDS片段按5'端第一个片段的相位去掉不完整的密码子
+fn to_record(head: &GffFeature, pieces: &[&GffFeature]) -> BedRecord {
+    let mut pieces = pieces.to_vec();
+    pieces.sort_by_key(|piece| piece.start);
+    let mut blocks: Vec<(i64, i64)> = pieces.iter().map(|piece| (piece.start - 1, piece.end)).collect();
+
+    if pieces.iter().all(|piece| piece.feature_type == "CDS") {
+        if head.reverse {
+            let phase = pieces.last().and_then(|piece| piece.phase).unwrap_or(0) as i64;
+            if let Some(block) = blocks.last_mut() {
+                block.1 -= phase;
+            }
+        } else {
+            let phase = pieces.first().and_then(|piece| piece.phase).unwrap_or(0) as i64;
+            if let Some(block) = blocks.first_mut() {
+                block.0 += phase;
+            }
+        }
+        blocks.retain(|&(start, end)| start < end);
+    }
+
+    let start = blocks.first().map(|block| block.0).unwrap_or(head.start - 1);
+    let end = blocks.last().map(|block| block.1).unwrap_or(head.end);
+    let name = head.id.clone()
+        .unwrap_or_else(|| format!("{}_{}_{}", head.seqid, head.start, head.end));
+
+    // 序列描述：特征类型、Name、Parent以及基因组坐标
+    let mut description = vec![head.feature_type.clone()];
+    if let Some(feature_name) = &head.name {
+        description.push(format!("Name={}", feature_name));
+    }
+    if !head.parents.is_empty() {
+        description.push(format!("Parent={}", head.parents.join(",")));
+    }
+    description.push(format!("{}:{}-{}({})", head.seqid, start + 1, end, if head.reverse { '-' } else { '+' }));
+
+    BedRecord {
+        chrom: head.seqid.clone(),
+        start,
+        end,
+        name: Some(name),
+        description: Some(description.join(" ")),
+        reverse: head.reverse,
+        thick_start: start,
+        thick_end: end,
+        blocks,
+    }
+}
+
+/// 特征类型是否在选择范围内；mRNA和transcript互为别名（GFF3多写mRNA，GTF多写transcript）
+fn is_selected(feature_type: &str, types: &[String]) -> bool {
+    types.iter().any(|t| t == feature_type || (is_transcript_type(t) && is_transcript_type(feature_type)))
+}
+
+/// 按类型选出GFF特征，并转换成splitBed使用的区间记录
+/// 转录本（mRNA/transcript）由其子特征part_type（CDS或exon）拼接；
+/// 其他类型中ID相同的多行特征（如GFF3中跨多个外显子的CDS）合并为一条
+/// 返回区间记录列表和错误信息
+pub fn select_features(features: &[GffFeature], types: &[String], part_type: &str) -> (Vec<BedRecord>, Vec<String>) {
+    let mut errors = Vec::new();
+
+    // 按Parent收集转录本的组分，同时记下每个Parent第一个组分在文件中的位置
+    let mut children: HashMap<&str, Vec<&GffFeature>> = HashMap::new();
+    let mut parent_order: Vec<(&str, usize)> = Vec::new();
+    for (line, feature) in features.iter().enumerate().filter(|(_, f)| f.feature_type == part_type) {
+        for parent in &feature.parents {
+            let pieces = children.entry(parent.as_str()).or_default();
+            if pieces.is_empty() {
+                parent_order.push((parent.as_str(), line));
+            }
+            pieces.push(feature);
+        }
+    }
+    let known_ids: HashSet<&str> = features.iter().filter_map(|f| f.id.as_deref()).collect();
+
+    // 按文件中首次出现的顺序收集需要输出的特征组，并记下首次出现的位置
+    let mut groups: Vec<(usize, &GffFeature, Vec<&GffFeature>)> = Vec::new();
+    let mut group_index: HashMap<(&str, &str), usize> = HashMap::new();
+    for (line, feature) in features.iter().enumerate().filter(|(_, f)| is_selected(&f.feature_type, types)) {
+        if is_transcript_type(&feature.feature_type) {
+            let id = match &feature.id {
+                Some(id) => id.as_str(),
+                None => {
+                    errors.push(format!("{} at {}:{}-{} has no ID", feature.feature_type, feature.seqid, feature.start, feature.end));
+                    continue;
+                }
+            };
+            match children.get(id) {
+                Some(pieces) => groups.push((line, feature, pieces.clone())),
+                None => errors.push(format!("No {} features found for {} {}", part_type, feature.feature_type, id)),
+            }
+            continue;
+        }
+
+        match &feature.id {
+            Some(id) => match group_index.get(&(feature.feature_type.as_str(), id.as_str())) {
+                Some(&index) => groups[index].2.push(feature),
+                None => {
+                    group_index.insert((feature.feature_type.as_str(), id.as_str()), groups.len());
+                    groups.push((line, feature, vec![feature]));
+                }
+            },
+            None => groups.push((line, feature, vec![feature])),
+        }
+    }
+
+    // 排序键为（文件中的位置，同一位置上补出的转录本排在组分之前）
+    let mut records: Vec<((usize, usize), BedRecord)> = groups.iter()
+        .map(|(line, head, pieces)| ((*line, 1), to_record(head, pieces)))
+        .collect();
+
+    // GTF常常省略transcript行，此时由组分的transcript_id直接组成转录本，放在第一个组分的位置上
+    if types.iter().any(|t| is_transcript_type(t)) {
+        for (parent, line) in parent_order.iter().filter(|(parent, _)| !known_ids.contains(parent)) {
+            let pieces = &children[parent];
+            let head = pieces[0];
+            let transcript = GffFeature {
+                seqid: head.seqid.clone(),
+                feature_type: "transcript".to_string(),
+                start: pieces.iter().map(|p| p.start).min().unwrap_or(head.start),
+                end: pieces.iter().map(|p| p.end).max().unwrap_or(head.end),
+                reverse: head.reverse,
+                phase: None,
+                id: Some(parent.to_string()),
+                name: head.name.clone(),
+                parents: Vec::new(),
+            };
+            records.push(((*line, 0), to_record(&transcript, pieces)));
+        }
+        records.sort_by_key(|(key, _)| *key);
+    }
+
+    (records.into_iter().map(|(_, record)| record).collect(), errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use seq_utils::gff::read_gff;
+    use crate::test_utils::write_temp;
+
+    const GTF_EXONS: &str = "\
+chr1\tsrc\texon\t11\t20\t.\t+\t.\tgene_id \"g1\"; transcript_id \"t1\"; gene_name \"ABC\";
+chr1\tsrc\tCDS\t14\t20\t.\t+\t0\tgene_id \"g1\"; transcript_id \"t1\";
+chr1\tsrc\texon\t31\t40\t.\t+\t.\tgene_id \"g1\"; transcript_id \"t1\";
+chr1\tsrc\tCDS\t31\t35\t.\t+\t2\tgene_id \"g1\"; transcript_id \"t1\";
+";
+
+    fn select(name: &str, content: &str, types: &[&str], part_type: &str) -> (Vec<BedRecord>, Vec<String>) {
+        let features = read_gff(&write_temp(name, content.as_bytes())).unwrap();
+        let types: Vec<String> = types.iter().map(|t| t.to_string()).collect();
+        select_features(&features, &types, part_type)
+    }
+
+    #[test]
+    fn test_gtf_without_transcript_lines() {
+        let (records, errors) = select("no_transcript.gtf", GTF_EXONS, &["mRNA"], "exon");
+        assert!(errors.is_empty());
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].name.as_deref(), Some("t1"));
+        assert_eq!(records[0].blocks, vec![(10, 20), (30, 40)]);
+        assert_eq!(records[0].description.as_deref(), Some("transcript Name=ABC chr1:11-40(+)"));
+    }
+
+    #[test]
+    fn test_gtf_with_transcript_lines() {
+        // 默认的--types mRNA也能选中GTF的transcript行
+        let content = format!("chr1\tsrc\ttranscript\t11\t40\t.\t+\t.\tgene_id \"g1\"; transcript_id \"t1\";\n{}", GTF_EXONS);
+        for types in [["mRNA"], ["transcript"]] {
+            let (records, errors) = select("with_transcript.gtf", &content, &types, "exon");
+            assert!(errors.is_empty());
+            assert_eq!(records.len(), 1);
+            assert_eq!(records[0].name.as_deref(), Some("t1"));
+            assert_eq!(records[0].blocks, vec![(10, 20), (30, 40)]);
+            assert_eq!(records[0].description.as_deref(), Some("transcript Parent=g1 chr1:11-40(+)"));
+        }
+    }
+
+    #[test]
+    fn test_cds_parts_trim_phase() {
+        // 5'端CDS片段相位为0，不裁剪；3'端片段的相位不影响区间
+        let (records, _) = select("cds.gtf", GTF_EXONS, &["transcript"], "CDS");
+        assert_eq!(records[0].blocks, vec![(13, 20), (30, 35)]);
+
+        let content = "chr1\tsrc\tCDS\t14\t20\t.\t-\t0\tgene_id \"g1\"; transcript_id \"t2\";\n\
+chr1\tsrc\tCDS\t31\t35\t.\t-\t1\tgene_id \"g1\"; transcript_id \"t2\";\n";
+        let (records, _) = select("cds_minus.gtf", content, &["transcript"], "CDS");
+        assert_eq!(records[0].blocks, vec![(13, 20), (30, 34)]);
+        assert!(records[0].reverse);
+    }
+
+    #[test]
+    fn test_gff3_features_merged_by_id() {
+        let content = "##gff-version 3
+chr1\tsrc\tgene\t1\t50\t.\t+\t.\tID=gene1;Name=ABC
+chr1\tsrc\tmRNA\t1\t50\t.\t+\t.\tID=mrna1;Parent=gene1
+chr1\tsrc\texon\t1\t10\t.\t+\t.\tParent=mrna1
+chr1\tsrc\tCDS\t5\t10\t.\t+\t0\tID=cds1;Parent=mrna1
+chr1\tsrc\texon\t41\t50\t.\t+\t.\tParent=mrna1
+chr1\tsrc\tCDS\t41\t45\t.\t+\t0\tID=cds1;Parent=mrna1
+chr1\tsrc\ttRNA\t60\t70\t.\t+\t.\tID=trna1
+chr1\tsrc\texon\t60\t70\t.\t+\t.\tParent=trna1
+";
+        let (records, errors) = select("merge.gff3", content, &["gene", "CDS"], "exon");
+        assert!(errors.is_empty());
+        let names: Vec<&str> = records.iter().map(|r| r.name.as_deref().unwrap()).collect();
+        assert_eq!(names, vec!["gene1", "cds1"]);
+        assert_eq!(records[1].blocks, vec![(4, 10), (40, 45)]);
+
+        // 非转录本的父特征（tRNA）不会被当作省略的transcript行
+        let (records, errors) = select("merge.gff3", content, &["mRNA"], "exon");
+        assert!(errors.is_empty());
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].blocks, vec![(0, 10), (40, 50)]);
+    }
+
+    #[test]
+    fn test_gtf_transcripts_keep_input_order() {
+        // 补出的转录本放在第一个外显子的位置上，与GFF3中mRNA行的顺序一致
+        let gtf = "\
+chr1\tsrc\tgene\t11\t40\t.\t+\t.\tgene_id \"g1\";
+chr1\tsrc\texon\t11\t20\t.\t+\t.\tgene_id \"g1\"; transcript_id \"t1\";
+chr1\tsrc\texon\t31\t40\t.\t+\t.\tgene_id \"g1\"; transcript_id \"t1\";
+chr1\tsrc\tgene\t61\t80\t.\t-\t.\tgene_id \"g2\";
+chr1\tsrc\texon\t61\t80\t.\t-\t.\tgene_id \"g2\"; transcript_id \"t2\";
+";
+        let gff3 = "##gff-version 3
+chr1\tsrc\tgene\t11\t40\t.\t+\t.\tID=g1
+chr1\tsrc\tmRNA\t11\t40\t.\t+\t.\tID=t1;Parent=g1
+chr1\tsrc\texon\t11\t20\t.\t+\t.\tParent=t1
+chr1\tsrc\texon\t31\t40\t.\t+\t.\tParent=t1
+chr1\tsrc\tgene\t61\t80\t.\t-\t.\tID=g2
+chr1\tsrc\tmRNA\t61\t80\t.\t-\t.\tID=t2;Parent=g2
+chr1\tsrc\texon\t61\t80\t.\t-\t.\tParent=t2
+";
+        for (name, content) in [("order.gtf", gtf), ("order.gff3", gff3)] {
+            let (records, errors) = select(name, content, &["gene", "mRNA"], "exon");
+            assert!(errors.is_empty());
+            let names: Vec<&str> = records.iter().map(|r| r.name.as_deref().unwrap()).collect();
+            assert_eq!(names, vec!["g1", "t1", "g2", "t2"], "{}", name);
+        }
+    }
+}
@@ -0,0 +1,171 @@
+use std::path::Path;
+
+/// 拆分成多个文件的方式
+pub enum PartMode {
+    Balanced(usize),   // 拆分成指定数量的文件，各文件总碱基数尽量接近
+    Limits {
+        max_records: Option<usize>, // 每个文件最多的序列条数
+        max_bases: Option<u64>,     // 每个文件最多的碱基数，单条超长序列单独成为一个文件
+    },
+    PerRecord,         // 每条序列一个文件
+}
+
+impl PartMode {
+    /// 顺序拆分（记录数/碱基数上限、每条序列一个文件）使用的分配器
+    /// 均衡拆分需要先知道全部序列的长度，返回None
+    pub fn limit_splitter(&self) -> Option<LimitSplitter> {
+        match self {
+            PartMode::Balanced(_) => None,
+            PartMode::Limits { max_records, max_bases } => Some(LimitSplitter::new(*max_records, *max_bases)),
+            PartMode::PerRecord => Some(LimitSplitter::new(Some(1), None)),
+        }
+    }
+}
+
+/// 均衡拆分时同时打开的输出文件数上限，避免耗尽文件描述符
+/// 文件数更多时分批写出，每批重新读取一遍输入
+pub const MAX_OPEN_PARTS: usize = 256;
+
+/// 清单中每个输出文件的信息
+pub struct PartInfo {
+    pub file: String,  // 输出文件路径
+    pub records: usize, // 序列条数
+    pub bases: u64,    // 碱基总数
+}
+
+/// 按总碱基数均衡分配：从长到短依次放入当前碱基数最少的文件，返回每条序列所属文件的编号（从0开始）
+/// 文件数量不超过序列条数，避免生成空文件
+pub fn assign_balanced(lengths: &[u64], parts: usize) -> Vec<usize> {
+    let parts = parts.min(lengths.len()).max(1);
+    let mut order: Vec<usize> = (0..lengths.len()).collect();
+    order.sort_by(|&a, &b| lengths[b].cmp(&lengths[a]).then(a.cmp(&b)));
+
+    let mut totals = vec![0u64; parts];
+    let mut assignments = vec![0; lengths.len()];
+    for i in order {
+        // 碱基数相同时选编号小的文件，保证结果可重复
+        let part = (0..parts).min_by_key(|&part| (totals[part], part)).expect("at least one part");
+        totals[part] += lengths[i];
+        assignments[i] = part;
+    }
+    assignments
+}
+
+/// 按记录数和碱基数上限依次分配序列，达到任一上限时换到下一个文件
+pub struct LimitSplitter {
+    max_records: Option<usize>,
+    max_bases: Option<u64>,
+    part: usize,
+    records: usize,
+    bases: u64,
+}
+
+impl LimitSplitter {
+    pub fn new(max_records: Option<usize>, max_bases: Option<u64>) -> Self {
+        LimitSplitter { max_records, max_bases, part: 0, records: 0, bases: 0 }
+    }
+
+    /// 返回下一条序列所属文件的编号（从0开始）
+    pub fn next_part(&mut self, length: u64) -> usize {
+        let records_full = self.max_records.is_some_and(|max| self.records >= max);
+        let bases_full = self.max_bases.is_some_and(|max| self.bases + length > max);
+        if self.records > 0 && (records_full || bases_full) {
+            self.part += 1;
+            self.records = 0;
+            self.bases = 0;
+        }
+        self.records += 1;
+        self.bases += length;
+        self.part
+    }
+}
+
+/// 输入文件名去掉目录、压缩后缀和序列格式后缀，用于文件名模板中的{stem}
+pub fn input_stem(input_path: &str) -> String {
+    let mut name = Path::new(input_path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    for suffix in [".gz", ".bgz", ".bgzf"] {
+        if let Some(stripped) = name.strip_suffix(suffix) {
+            name = stripped.to_string();
+            break;
+        }
+    }
+    for suffix in [".fasta", ".fa", ".fna", ".fastq", ".fq"] {
+        if let Some(stripped) = name.strip_suffix(suffix) {
+            name = stripped.to_string();
+            break;
+        }
+    }
+    name
+}
+
+/// 未指定--template时的默认文件名模板，FASTQ输入使用.fq后缀
+pub fn default_template(mode: &PartMode, fastq: bool) -> String {
+    let suffix = if fastq { "fq" } else { "fa" };
+    match mode {
+        PartMode::PerRecord => format!("{{id}}.{}", suffix),
+        _ => format!("{{stem}}.part_{{index}}.{}", suffix),
+    }
+}
+
+/// 按模板生成文件名：{stem}为输入文件名，{index}为从1开始的文件编号，{id}为文件中第一条序列的标识符
+/// 标识符中的路径分隔符替换为下划线
+pub fn part_file_name(template: &str, stem: &str, index: usize, id: &str) -> String {
+    template
+        .replace("{stem}", stem)
+        .replace("{index}", &index.to_string())
+        .replace("{id}", &id.replace(['/', '\\'], "_"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn totals(lengths: &[u64], assignments: &[usize], parts: usize) -> Vec<u64> {
+        let mut totals = vec![0; parts];
+        for (&length, &part) in lengths.iter().zip(assignments) {
+            totals[part] += length;
+        }
+        totals
+    }
+
+    #[test]
+    fn test_assign_balanced() {
+        let lengths = [10, 70, 20, 30, 40, 30];
+        let assignments = assign_balanced(&lengths, 2);
+        assert_eq!(assignments, vec![1, 0, 1, 1, 1, 0]);
+        assert_eq!(totals(&lengths, &assignments, 2), vec![100, 100]);
+
+        // 文件数不超过序列条数
+        let assignments = assign_balanced(&[5, 3], 4);
+        assert_eq!(assignments, vec![0, 1]);
+        assert!(assign_balanced(&[], 3).is_empty());
+    }
+
+    #[test]
+    fn test_limit_splitter() {
+        let mut splitter = LimitSplitter::new(Some(2), Some(100));
+        let parts: Vec<usize> = [10, 20, 30, 90, 150, 5].iter().map(|&length| splitter.next_part(length)).collect();
+        // 记录数达到上限、碱基数超过上限时换文件；超长序列单独成为一个文件
+        assert_eq!(parts, vec![0, 0, 1, 2, 3, 4]);
+
+        let mut splitter = PartMode::PerRecord.limit_splitter().unwrap();
+        let parts: Vec<usize> = [10, 20, 30].iter().map(|&length| splitter.next_part(length)).collect();
+        assert_eq!(parts, vec![0, 1, 2]);
+        assert!(PartMode::Balanced(2).limit_splitter().is_none());
+    }
+
+    #[test]
+    fn test_file_names() {
+        assert_eq!(input_stem("data/genome.fa.gz"), "genome");
+        assert_eq!(input_stem("reads.fastq"), "reads");
+        assert_eq!(input_stem("assembly.v2.fna.bgz"), "assembly.v2");
+        assert_eq!(part_file_name("{stem}.part_{index}.fa", "genome", 3, "chr1"), "genome.part_3.fa");
+        assert_eq!(part_file_name("{id}.fa", "genome", 1, "scaf/1"), "scaf_1.fa");
+        assert_eq!(default_template(&PartMode::Balanced(2), false), "{stem}.part_{index}.fa");
+        assert_eq!(default_template(&PartMode::Balanced(2), true), "{stem}.part_{index}.fq");
+        assert_eq!(default_template(&PartMode::PerRecord, true), "{id}.fq");
+    }
+}
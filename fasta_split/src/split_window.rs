@@ -0,0 +1,131 @@
+use crate::fasta::FastaSequence;
+use crate::split::{FragmentStatus, SplitPosition};
+
+/// 按固定窗口切割的参数
+pub struct WindowOptions {
+    pub window: usize,                 // 窗口长度
+    pub step: usize,                   // 相邻窗口起点的距离，小于窗口长度时窗口互相重叠
+    pub max_n_fraction: Option<f64>,   // N的比例超过该值的窗口被跳过
+}
+
+impl WindowOptions {
+    /// 检查窗口参数是否合法
+    pub fn validate(&self) -> Result<(), String> {
+        if self.window == 0 {
+            return Err("Window size must be greater than 0".to_string());
+        }
+        if self.step == 0 {
+            return Err("Window step must be greater than 0; overlap must be smaller than the window".to_string());
+        }
+        if let Some(fraction) = self.max_n_fraction {
+            if !(0.0..=1.0).contains(&fraction) {
+                return Err(format!("Maximum N fraction must be between 0 and 1: {}", fraction));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 将单条序列切割成固定长度的窗口，最后一个窗口可能短于窗口长度
+/// 窗口按在序列上的顺序编号，跳过的窗口同样占用编号，因此窗口标识符不随--max-n改变
+/// 返回一个元组，包含窗口序列列表和窗口位置列表（含跳过的窗口）
+pub fn split_windows(seq: &FastaSequence, options: &WindowOptions) -> (Vec<FastaSequence>, Vec<SplitPosition>) {
+    let mut new_sequences = Vec::new();
+    let mut split_positions = Vec::new();
+
+    let length = seq.sequence.len();
+    let bases = seq.sequence.as_bytes();
+    let mut start = 0;
+    let mut window_count = 1;
+
+    while start < length {
+        let end = (start + options.window).min(length);
+
+        // N比例过高的窗口被跳过
+        let n_count = bases[start..end].iter().filter(|&&c| c == b'N' || c == b'n').count();
+        let mostly_n = options.max_n_fraction
+            .is_some_and(|fraction| n_count as f64 > fraction * (end - start) as f64);
+
+        let (status, new_id) = if mostly_n {
+            (FragmentStatus::Dropped, ".".to_string())
+        } else {
+            let new_id = format!("{}_{}", seq.id, window_count);
+            new_sequences.push(FastaSequence {
+                id: new_id.clone(),
+                description: seq.description.clone(),
+                sequence: seq.sequence[start..end].to_string(),
+                quality: seq.quality.as_ref().map(|q| q[start..end].to_string()),
+            });
+            (FragmentStatus::Kept, new_id)
+        };
+        window_count += 1;
+
+        split_positions.push(SplitPosition {
+            id: seq.id.clone(),
+            start: start + 1,
+            end,
+            new_id,
+            gap_after: 0,
+            status,
+            cut_by: ".",
+        });
+
+        if end == length {
+            break;
+        }
+        start += options.step;
+    }
+
+    (new_sequences, split_positions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sequence(bases: &str) -> FastaSequence {
+        FastaSequence { id: "chr1".to_string(), description: String::new(), sequence: bases.to_string(), quality: None }
+    }
+
+    fn options(window: usize, step: usize, max_n_fraction: Option<f64>) -> WindowOptions {
+        WindowOptions { window, step, max_n_fraction }
+    }
+
+    fn coordinates(positions: &[SplitPosition]) -> Vec<(usize, usize, &str)> {
+        positions.iter().map(|p| (p.start, p.end, p.new_id.as_str())).collect()
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(options(10, 5, Some(0.5)).validate().is_ok());
+        assert!(options(0, 5, None).validate().is_err());
+        assert!(options(10, 0, None).validate().is_err());
+        assert!(options(10, 5, Some(1.5)).validate().is_err());
+    }
+
+    #[test]
+    fn test_fixed_windows() {
+        let (windows, positions) = split_windows(&sequence("ACGTACGTAC"), &options(4, 4, None));
+        let bases: Vec<&str> = windows.iter().map(|w| w.sequence.as_str()).collect();
+        assert_eq!(bases, vec!["ACGT", "ACGT", "AC"]);
+        assert_eq!(coordinates(&positions), vec![(1, 4, "chr1_1"), (5, 8, "chr1_2"), (9, 10, "chr1_3")]);
+    }
+
+    #[test]
+    fn test_overlapping_windows_stop_at_sequence_end() {
+        let (windows, positions) = split_windows(&sequence("ACGTACGTAC"), &options(4, 3, None));
+        let bases: Vec<&str> = windows.iter().map(|w| w.sequence.as_str()).collect();
+        assert_eq!(bases, vec!["ACGT", "TACG", "GTAC"]);
+        assert_eq!(coordinates(&positions), vec![(1, 4, "chr1_1"), (4, 7, "chr1_2"), (7, 10, "chr1_3")]);
+    }
+
+    #[test]
+    fn test_mostly_n_windows_keep_their_numbers() {
+        // 跳过的窗口占用编号，后面的窗口编号不变
+        let (windows, positions) = split_windows(&sequence("ACGTNNNNACNN"), &options(4, 4, Some(0.5)));
+        let ids: Vec<&str> = windows.iter().map(|w| w.id.as_str()).collect();
+        assert_eq!(ids, vec!["chr1_1", "chr1_3"]);
+        assert_eq!(coordinates(&positions), vec![(1, 4, "chr1_1"), (5, 8, "."), (9, 12, "chr1_3")]);
+        assert!(positions[1].status == FragmentStatus::Dropped);
+    }
+}
@@ -1,58 +1,220 @@
 use crate::fasta::FastaSequence;
+use crate::split::{self, BreakRule, SplitPosition};
 
 /// 代表序列统计结果的结构体
 pub struct SequenceStats {
     pub id: String,         // 序列标识符
     pub total_length: i64,  // 序列总长度（使用int64）
     pub n_count: usize,     // N的数量
+    pub break_rule: &'static str, // 切割规则
+    pub break_count: usize,  // 被切除的区间数
+    pub break_length: usize, // 被切除的碱基总数
 }
 
-/// 计算给定序列列表的统计信息
-/// 返回一个包含所有序列统计信息的向量
-pub fn calculate_stats(sequences: &Vec<FastaSequence>) -> Vec<SequenceStats> {
-    sequences.iter().map(|seq| {
-        let total_length = seq.sequence.len() as i64;
-        let n_count = seq.sequence.chars().filter(|&c| c == 'N' || c == 'n').count();
-        SequenceStats {
-            id: seq.id.clone(),
+/// 计算单条序列的统计信息，切割信息来自该序列的切割位置记录
+pub fn calculate_stats(seq: &FastaSequence, positions: &[SplitPosition], break_rule: &'static str) -> SequenceStats {
+    let total_length = seq.sequence.len() as i64;
+    let n_count = seq.sequence.bytes().filter(|&c| c == b'N' || c == b'n').count();
+    let (break_count, break_length) = split::count_breaks(seq.sequence.len(), positions);
+    SequenceStats {
+        id: seq.id.clone(),
+        total_length,
+        n_count,
+        break_rule,
+        break_count,
+        break_length,
+    }
+}
+
+/// 代表整个组装统计汇总的结构体
+pub struct AssemblySummary {
+    pub sequence_count: usize, // 序列条数
+    pub total_length: u64,     // 总长度
+    pub n50: u64,              // N50
+    pub l50: usize,            // L50
+    pub n90: u64,              // N90
+    pub l90: usize,            // L90
+    pub au_n: f64,             // auN（长度加权的平均长度）
+    pub longest: u64,          // 最长序列长度
+    pub shortest: u64,         // 最短序列长度
+    pub gc_percent: f64,       // GC含量（不计N等非ACGT字符）
+    pub gap_count: usize,      // gap（满足切割规则的区间）数量
+    pub gap_length: u64,       // gap总长度
+}
+
+/// 组装统计的累加器，逐条加入序列，最后计算汇总结果
+/// 只保存每条序列的长度，不保存序列本身
+#[derive(Default)]
+pub struct AssemblyStats {
+    lengths: Vec<u64>,
+    gc_count: u64,
+    acgt_count: u64,
+    gap_count: usize,
+    gap_length: u64,
+}
+
+impl AssemblyStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 加入一条序列；满足切割规则且长度不小于min_gap的区间计为gap
+    pub fn add(&mut self, sequence: &str, break_rule: &BreakRule, min_gap: usize) {
+        self.lengths.push(sequence.len() as u64);
+
+        for &c in sequence.as_bytes() {
+            match c {
+                b'G' | b'C' | b'g' | b'c' => {
+                    self.gc_count += 1;
+                    self.acgt_count += 1;
+                }
+                b'A' | b'T' | b'a' | b't' => self.acgt_count += 1,
+                _ => {}
+            }
+        }
+        for (run_start, run_end) in break_rule.find_runs(sequence) {
+            if run_end - run_start >= min_gap {
+                self.gap_count += 1;
+                self.gap_length += (run_end - run_start) as u64;
+            }
+        }
+    }
+
+    /// 计算汇总结果
+    pub fn summary(&self) -> AssemblySummary {
+        let mut lengths = self.lengths.clone();
+        lengths.sort_unstable_by(|a, b| b.cmp(a));
+        let total_length: u64 = lengths.iter().sum();
+
+        // 从长到短累加，累计长度达到总长度的指定比例时的序列长度和条数
+        let nx = |fraction: f64| -> (u64, usize) {
+            let threshold = total_length as f64 * fraction;
+            let mut cumulative = 0;
+            for (i, &length) in lengths.iter().enumerate() {
+                cumulative += length;
+                if cumulative as f64 >= threshold {
+                    return (length, i + 1);
+                }
+            }
+            (0, 0)
+        };
+        let (n50, l50) = nx(0.5);
+        let (n90, l90) = nx(0.9);
+
+        let au_n = if total_length > 0 {
+            lengths.iter().map(|&length| (length as f64) * (length as f64)).sum::<f64>() / total_length as f64
+        } else {
+            0.0
+        };
+        let gc_percent = if self.acgt_count > 0 {
+            self.gc_count as f64 * 100.0 / self.acgt_count as f64
+        } else {
+            0.0
+        };
+
+        AssemblySummary {
+            sequence_count: lengths.len(),
             total_length,
-            n_count,
+            n50,
+            l50,
+            n90,
+            l90,
+            au_n,
+            longest: lengths.first().copied().unwrap_or(0),
+            shortest: lengths.last().copied().unwrap_or(0),
+            gc_percent,
+            gap_count: self.gap_count,
+            gap_length: self.gap_length,
         }
-    }).collect()
+    }
 }
 
-//#[cfg(test)]
-//mod tests {
-//    use super::*;
-
-//    #[test]
-//    fn test_calculate_stats() {
-//        // 创建测试FASTA序列
-//        let sequences = vec![
-//            FastaSequence {
-//                id: "seq1".to_string(),
-//                description: "description1".to_string(),
-//                sequence: "ATCGNNNNATCG".to_string(),
-//            },
-//            FastaSequence {
-//                id: "seq2".to_string(),
-//                description: "description2".to_string(),
-//                sequence: "GCTAGCTA".to_string(),
-//            }
-//        ];
-
-//        // 计算统计信息
-//        let stats = calculate_stats(&sequences);
-//        assert_eq!(stats.len(), 2);
-
-//        // 验证第一条序列的统计信息
-//        assert_eq!(stats[0].id, "seq1");
-//        assert_eq!(stats[0].total_length, 12);
-//        assert_eq!(stats[0].n_count, 4);
-
-//        // 验证第二条序列的统计信息
-//        assert_eq!(stats[1].id, "seq2");
-//        assert_eq!(stats[1].total_length, 8);
-//        assert_eq!(stats[1].n_count, 0);
-//    }
-//}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::split::{split_sequence, BreakRule, SplitOptions};
+
+    fn sequence(id: &str, bases: &str) -> FastaSequence {
+        FastaSequence { id: id.to_string(), description: String::new(), sequence: bases.to_string(), quality: None }
+    }
+
+    #[test]
+    fn test_calculate_stats() {
+        let options = SplitOptions { break_rule: BreakRule::N, min_gap: 1, min_length: 0, keep_short: false };
+
+        // 验证第一条序列的统计信息
+        let seq1 = sequence("seq1", "ATCGNNNNATCG");
+        let (_, positions) = split_sequence(&seq1, &options);
+        let stats = calculate_stats(&seq1, &positions, "N");
+        assert_eq!(stats.id, "seq1");
+        assert_eq!(stats.total_length, 12);
+        assert_eq!(stats.n_count, 4);
+        assert_eq!((stats.break_count, stats.break_length), (1, 4));
+
+        // 验证第二条序列的统计信息
+        let seq2 = sequence("seq2", "GCTAGCTA");
+        let (_, positions) = split_sequence(&seq2, &options);
+        let stats = calculate_stats(&seq2, &positions, "N");
+        assert_eq!(stats.id, "seq2");
+        assert_eq!(stats.total_length, 8);
+        assert_eq!(stats.n_count, 0);
+        assert_eq!((stats.break_count, stats.break_length), (0, 0));
+    }
+
+    #[test]
+    fn test_n50_l50_and_au_n() {
+        let mut stats = AssemblyStats::new();
+        for length in [2, 3, 4, 5, 6, 10, 70] {
+            stats.add(&"A".repeat(length), &BreakRule::N, 1);
+        }
+        let summary = stats.summary();
+        assert_eq!(summary.sequence_count, 7);
+        assert_eq!(summary.total_length, 100);
+        assert_eq!((summary.n50, summary.l50), (70, 1));
+        assert_eq!((summary.n90, summary.l90), (5, 4));
+        assert_eq!((summary.longest, summary.shortest), (70, 2));
+        // auN = sum(L^2) / total
+        assert!((summary.au_n - 50.9).abs() < 1e-9);
+
+        // 累计长度恰好等于一半时取当前序列
+        let mut stats = AssemblyStats::new();
+        for length in [4, 4, 2, 2, 2, 2] {
+            stats.add(&"A".repeat(length), &BreakRule::N, 1);
+        }
+        let summary = stats.summary();
+        assert_eq!((summary.n50, summary.l50), (4, 2));
+    }
+
+    #[test]
+    fn test_gc_and_gaps() {
+        let mut stats = AssemblyStats::new();
+        stats.add("GGCCNNNAATTNacgt", &BreakRule::N, 2);
+        let summary = stats.summary();
+        assert!((summary.gc_percent - 50.0).abs() < 1e-9);
+        assert_eq!((summary.gap_count, summary.gap_length), (1, 3));
+    }
+
+    #[test]
+    fn test_gaps_follow_break_rule() {
+        // 小写区间和简并碱基区间按所选规则计为gap，N区间不再单独计数
+        let sequence = "ACGTacgtNNACRYTTacGT";
+        let count = |rule: &BreakRule| {
+            let mut stats = AssemblyStats::new();
+            stats.add(sequence, rule, 1);
+            let summary = stats.summary();
+            (summary.gap_count, summary.gap_length)
+        };
+        assert_eq!(count(&BreakRule::N), (1, 2));
+        assert_eq!(count(&BreakRule::Lowercase), (2, 6));
+        assert_eq!(count(&BreakRule::Iupac), (2, 4));
+        assert_eq!(count(&BreakRule::Regex(regex::Regex::new("TT").unwrap())), (1, 2));
+    }
+
+    #[test]
+    fn test_empty_summary() {
+        let summary = AssemblyStats::new().summary();
+        assert_eq!(summary.sequence_count, 0);
+        assert_eq!((summary.n50, summary.l50), (0, 0));
+        assert_eq!(summary.au_n, 0.0);
+    }
+}
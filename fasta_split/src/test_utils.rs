@@ -0,0 +1,16 @@
+use std::fs;
+use std::path::PathBuf;
+
+/// 测试用的临时文件路径，按进程号和名称区分，避免并行测试互相覆盖
+pub fn temp_path(name: &str) -> String {
+    let dir: PathBuf = std::env::temp_dir().join(format!("fasta_split_test_{}", std::process::id()));
+    fs::create_dir_all(&dir).expect("create temp dir");
+    dir.join(name).to_string_lossy().into_owned()
+}
+
+/// 把内容写入临时文件并返回路径
+pub fn write_temp(name: &str, content: &[u8]) -> String {
+    let path = temp_path(name);
+    fs::write(&path, content).expect("write temp file");
+    path
+}
@@ -0,0 +1,164 @@
+use crate::open_input;
+use std::io::{self, BufRead};
+
+/// 代表GFF3/GTF中一行特征的结构体
+/// GFF3和GTF的属性写法不同，解析时统一成ID、Name、Parent三项
+pub struct GffFeature {
+    pub seqid: String,         // 所在序列ID
+    pub feature_type: String,  // 特征类型（第3列）
+    pub start: i64,            // 起始位置（1-based，闭区间）
+    pub end: i64,              // 终止位置（闭区间）
+    pub reverse: bool,         // 链方向为'-'时为true
+    pub phase: Option<usize>,  // CDS的相位（第8列，0-2）
+    pub id: Option<String>,    // GFF3的ID；GTF中gene取gene_id，transcript/mRNA取transcript_id
+    pub name: Option<String>,  // GFF3的Name；GTF中取gene_name
+    pub parents: Vec<String>,  // GFF3的Parent；GTF中transcript取gene_id，其他取transcript_id
+}
+
+fn invalid_gff(line_number: usize, message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("Invalid GFF line {}: {}", line_number, message))
+}
+
+/// 判断特征是否为转录本（mRNA或transcript）
+pub fn is_transcript_type(feature_type: &str) -> bool {
+    feature_type == "mRNA" || feature_type == "transcript"
+}
+
+/// 解析GFF3属性列：key=value;key=value，多个值用逗号分隔
+fn parse_gff3_attributes(field: &str) -> Vec<(String, String)> {
+    field.split(';')
+        .filter_map(|pair| pair.trim().split_once('='))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+/// 解析GTF属性列：key "value"; key "value";
+fn parse_gtf_attributes(field: &str) -> Vec<(String, String)> {
+    field.split(';')
+        .filter_map(|pair| pair.trim().split_once(' '))
+        .map(|(key, value)| (key.to_string(), value.trim().trim_matches('"').to_string()))
+        .collect()
+}
+
+/// 从指定文件路径读取并解析GFF3或GTF文件，逐行自动识别属性格式
+/// 遇到GFF3的##FASTA段时停止读取
+pub fn read_gff(file_path: &str) -> io::Result<Vec<GffFeature>> {
+    let reader = open_input(file_path)?;
+    let mut features = Vec::new();
+
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line_number = index + 1;
+        if line.starts_with("##FASTA") {
+            break;
+        }
+        if line.starts_with('#') || line.trim().is_empty() {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.trim_end().split('\t').collect();
+        if fields.len() < 9 {
+            return Err(invalid_gff(line_number, "expected 9 columns"));
+        }
+        let start: i64 = fields[3].parse().map_err(|_| invalid_gff(line_number, "invalid start"))?;
+        let end: i64 = fields[4].parse().map_err(|_| invalid_gff(line_number, "invalid end"))?;
+        if start < 1 || end < start {
+            return Err(invalid_gff(line_number, "start and end out of order"));
+        }
+        let reverse = match fields[6] {
+            "-" => true,
+            "+" | "." | "?" => false,
+            _ => return Err(invalid_gff(line_number, "invalid strand")),
+        };
+        let phase = match fields[7] {
+            "." => None,
+            value => Some(value.parse().ok().filter(|phase| *phase <= 2).ok_or_else(|| invalid_gff(line_number, "invalid phase"))?),
+        };
+
+        // GFF3的第一个属性形如key=value，GTF形如key "value"
+        let attributes_field = fields[8];
+        let is_gff3 = attributes_field.split(';').next()
+            .is_some_and(|pair| pair.contains('=') && !pair.trim().contains(' '));
+        let feature_type = fields[2];
+        let (id, name, parents) = if is_gff3 {
+            let attributes = parse_gff3_attributes(attributes_field);
+            let get = |key: &str| attributes.iter().find(|(k, _)| k == key).map(|(_, v)| v.clone());
+            let parents = get("Parent")
+                .map(|parent| parent.split(',').map(|p| p.to_string()).collect())
+                .unwrap_or_default();
+            (get("ID"), get("Name"), parents)
+        } else {
+            let attributes = parse_gtf_attributes(attributes_field);
+            let get = |key: &str| attributes.iter().find(|(k, _)| k == key).map(|(_, v)| v.clone());
+            let (id, parent) = match feature_type {
+                "gene" => (get("gene_id"), None),
+                t if is_transcript_type(t) => (get("transcript_id"), get("gene_id")),
+                _ => (None, get("transcript_id")),
+            };
+            (id, get("gene_name"), parent.into_iter().collect())
+        };
+
+        features.push(GffFeature {
+            seqid: fields[0].to_string(),
+            feature_type: feature_type.to_string(),
+            start,
+            end,
+            reverse,
+            phase,
+            id,
+            name,
+            parents,
+        });
+    }
+
+    Ok(features)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_temp(name: &str, content: &str) -> String {
+        let dir = std::env::temp_dir().join(format!("seq_utils_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        std::fs::write(&path, content).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn test_read_gff3_and_gtf_attributes() {
+        let path = write_temp("mixed.gff", "##gff-version 3
+chr1\tsrc\tmRNA\t1\t50\t.\t+\t.\tID=tx1;Parent=gene1,gene2;Name=ABC
+chr1\tsrc\tCDS\t5\t10\t.\t-\t2\tParent=tx1
+chr2\tsrc\ttranscript\t3\t9\t.\t.\t.\tgene_id \"g1\"; transcript_id \"t1\"; gene_name \"XYZ\";
+chr2\tsrc\texon\t3\t9\t.\t+\t.\tgene_id \"g1\"; transcript_id \"t1\";
+##FASTA
+>chr1
+");
+        let features = read_gff(&path).unwrap();
+        assert_eq!(features.len(), 4);
+        assert_eq!(features[0].id.as_deref(), Some("tx1"));
+        assert_eq!(features[0].name.as_deref(), Some("ABC"));
+        assert_eq!(features[0].parents, vec!["gene1", "gene2"]);
+        assert!(features[1].reverse);
+        assert_eq!(features[1].phase, Some(2));
+        assert_eq!((features[2].id.as_deref(), features[2].parents.as_slice()), (Some("t1"), &["g1".to_string()][..]));
+        assert_eq!(features[2].name.as_deref(), Some("XYZ"));
+        assert_eq!((features[3].id.as_deref(), features[3].parents.as_slice()), (None, &["t1".to_string()][..]));
+        assert!(is_transcript_type(&features[2].feature_type));
+    }
+
+    #[test]
+    fn test_read_gff_rejects_invalid_lines() {
+        for (name, line) in [
+            ("columns.gff3", "chr1\tsrc\tCDS\t1\t8\t.\t+\t0\n"),
+            ("order.gff3", "chr1\tsrc\tCDS\t8\t1\t.\t+\t0\tParent=tx1\n"),
+            ("strand.gff3", "chr1\tsrc\tCDS\t1\t8\t.\tx\t0\tParent=tx1\n"),
+            ("phase.gff3", "chr1\tsrc\tCDS\t1\t8\t.\t+\t3\tParent=tx1\n"),
+        ] {
+            let error = read_gff(&write_temp(name, line)).err().unwrap();
+            assert!(error.to_string().starts_with("Invalid GFF line 1"), "{}", name);
+        }
+    }
+}
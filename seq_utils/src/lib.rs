@@ -0,0 +1,51 @@
+pub mod gff;
+
+use flate2::read::MultiGzDecoder;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+
+/// gzip文件的魔数（bgzip是多成员gzip，魔数相同）
+pub const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// 打开输入文件，根据文件头的魔数自动识别gzip/bgzip压缩
+/// 返回统一的缓冲读取器，调用方无需关心文件是否压缩
+pub fn open_input(file_path: &str) -> io::Result<Box<dyn BufRead>> {
+    let mut reader = BufReader::new(File::open(file_path)?);
+    let is_gzip = reader.fill_buf()?.starts_with(&GZIP_MAGIC);
+    if is_gzip {
+        // MultiGzDecoder会依次解压所有成员，因此同样适用于bgzip
+        Ok(Box::new(BufReader::new(MultiGzDecoder::new(reader))))
+    } else {
+        Ok(Box::new(reader))
+    }
+}
+
+/// 求单个碱基的互补碱基，支持IUPAC简并碱基并保留大小写
+pub fn complement(base: u8) -> u8 {
+    match base {
+        b'A' => b'T', b'T' => b'A', b'U' => b'A', b'G' => b'C', b'C' => b'G',
+        b'R' => b'Y', b'Y' => b'R', b'K' => b'M', b'M' => b'K',
+        b'B' => b'V', b'V' => b'B', b'D' => b'H', b'H' => b'D',
+        b'a' => b't', b't' => b'a', b'u' => b'a', b'g' => b'c', b'c' => b'g',
+        b'r' => b'y', b'y' => b'r', b'k' => b'm', b'm' => b'k',
+        b'b' => b'v', b'v' => b'b', b'd' => b'h', b'h' => b'd',
+        // S、W、N以及其他字符的互补是其本身
+        other => other,
+    }
+}
+
+/// 求序列的反向互补序列
+pub fn reverse_complement(sequence: &str) -> String {
+    sequence.bytes().rev().map(|base| complement(base) as char).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reverse_complement() {
+        assert_eq!(reverse_complement("ACGTNacgtRY"), "RYacgtNACGT");
+        assert_eq!(reverse_complement("AUSW"), "WSAT");
+    }
+}